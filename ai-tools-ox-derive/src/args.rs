@@ -0,0 +1,92 @@
+use darling::{ast, FromDeriveInput, FromField};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::serde::{SerdeAttrs, SerdeCase};
+
+#[derive(Debug, FromField)]
+#[darling(forward_attrs(serde))]
+struct ArgsField {
+    ident: Option<syn::Ident>,
+    attrs: Vec<syn::Attribute>,
+}
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(supports(struct_named), forward_attrs(serde))]
+struct ArgsReceiver {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    attrs: Vec<syn::Attribute>,
+    data: ast::Data<(), ArgsField>,
+}
+
+/// Reads every field with `args::field`, following the serde attributes
+/// that change the argument names and defaults.
+fn derive(input: &syn::DeriveInput) -> darling::Result<TokenStream> {
+    let receiver = ArgsReceiver::from_derive_input(input)?;
+    let container = SerdeAttrs::from_attrs(&receiver.attrs);
+    let rename_all = container
+        .rename_all
+        .as_ref()
+        .map(SerdeCase::from_lit)
+        .transpose()?;
+    let fields = receiver.data.take_struct().unwrap().fields;
+    let fields = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let serde = SerdeAttrs::from_attrs(&field.attrs);
+        let default = match &serde.default_fn {
+            Some(path) => quote!(#path()),
+            None => quote!(::core::default::Default::default()),
+        };
+        if serde.skip {
+            return quote!(#ident: #default);
+        }
+        let name = serde.rename.unwrap_or_else(|| {
+            let name = ident.to_string();
+            match rename_all {
+                Some(case) => case.apply_to_field(&name),
+                None => name,
+            }
+        });
+        let value = quote!(::ai_tools_ox::args::field(&mut args, #name)?);
+        match serde.default || container.default {
+            true => quote! {
+                #ident: match args.contains_key(#name) {
+                    true => #value,
+                    false => #default,
+                }
+            },
+            false => quote!(#ident: #value),
+        }
+    });
+
+    let mut generics = receiver.generics.clone();
+    let bounds = receiver
+        .generics
+        .type_params()
+        .map(|param| -> syn::WherePredicate {
+            let ident = &param.ident;
+            syn::parse_quote!(#ident: ::ai_tools_ox::__private::serde::de::DeserializeOwned)
+        })
+        .collect::<Vec<_>>();
+    generics.make_where_clause().predicates.extend(bounds);
+    let (imp, ty, wher) = generics.split_for_impl();
+    let ident = &receiver.ident;
+    Ok(quote! {
+        impl #imp ::ai_tools_ox::args::FromToolArgs for #ident #ty #wher {
+            fn from_args(
+                value: ::ai_tools_ox::__private::serde_json::Value,
+            ) -> Result<Self, ::ai_tools_ox::args::ArgsError> {
+                #[allow(unused_mut)]
+                let mut args = ::ai_tools_ox::args::object(value)?;
+                Ok(Self {
+                    #(#fields),*
+                })
+            }
+        }
+    })
+}
+
+pub fn expand(input: &syn::DeriveInput) -> TokenStream {
+    derive(input).unwrap_or_else(|e| e.write_errors())
+}
@@ -1,12 +1,62 @@
+mod args;
 mod object;
+mod serde;
+mod tool;
 
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(Object, attributes(description))]
+#[proc_macro_derive(Object, attributes(description, object, serde, tool))]
 pub fn derive_object(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     object::expand(&ast).into()
 }
+
+/// Implements `EnumJsonify` for an enum without data.
+#[proc_macro_derive(EnumJsonify, attributes(description, object, serde))]
+pub fn derive_enum_jsonify(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    object::expand_enum(&ast).into()
+}
+
+/// Implements `FromToolArgs`, reporting which argument failed to parse.
+#[proc_macro_derive(FromToolArgs, attributes(serde))]
+pub fn derive_from_tool_args(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    args::expand(&ast).into()
+}
+
+/// Implements `ToTool` by parsing the arguments of the type's handler
+/// method with `FromToolArgs`, `run` unless set with
+/// `#[tool(handler = "...")]`.
+#[proc_macro_derive(ToTool, attributes(tool))]
+pub fn derive_to_tool(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    tool::expand_derive(&ast).into()
+}
+
+/// Turns a function into a tool. Generates a unit struct named after the
+/// function with a `Tool` suffix that implements `ToTool`.
+#[proc_macro_attribute]
+pub fn tool(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as syn::ItemFn);
+    tool::expand(args.into(), function).into()
+}
+
+/// Builds the `Tool` definition of a function marked `#[tool]`, e.g.
+/// `tool_from_fn!(get_weather)`.
+#[proc_macro]
+pub fn tool_from_fn(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as syn::Path);
+    tool::expand_from_fn(path).into()
+}
+
+/// On an inherent impl block, generates `toolbox(self: Arc<Self>) -> Tools`
+/// exposing every method marked `#[tool]`.
+#[proc_macro_attribute]
+pub fn toolbox(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let block = parse_macro_input!(item as syn::ItemImpl);
+    tool::expand_toolbox(block).into()
+}
@@ -1,23 +1,747 @@
-use darling::{ast, util, FromDeriveInput, FromField};
-use proc_macro2::{Ident, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{Data, DeriveInput, Fields};
+use darling::{ast, FromDeriveInput, FromField, FromMeta, FromVariant};
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase,
+    ToUpperCamelCase,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{spanned::Spanned, DeriveInput};
+
+use crate::{
+    serde::{SerdeAttrs, SerdeCase},
+    tool::check_definition,
+};
+
+#[derive(Debug, Clone, Copy, FromMeta)]
+pub(crate) enum RenameRule {
+    #[darling(rename = "lowercase")]
+    Lower,
+    #[darling(rename = "UPPERCASE")]
+    Upper,
+    #[darling(rename = "camelCase")]
+    Camel,
+    #[darling(rename = "snake_case")]
+    Snake,
+    #[darling(rename = "PascalCase")]
+    Pascal,
+    #[darling(rename = "kebab-case")]
+    Kebab,
+    #[darling(rename = "SCREAMING_SNAKE_CASE")]
+    ScreamingSnake,
+    #[darling(rename = "SCREAMING-KEBAB-CASE")]
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    pub(crate) fn apply(self, name: &str) -> String {
+        match self {
+            RenameRule::Camel => name.to_lower_camel_case(),
+            RenameRule::Snake => name.to_snake_case(),
+            RenameRule::Pascal => name.to_upper_camel_case(),
+            RenameRule::Kebab => name.to_kebab_case(),
+            RenameRule::Lower => name.to_lowercase(),
+            RenameRule::Upper => name.to_uppercase(),
+            RenameRule::ScreamingSnake => name.to_shouty_snake_case(),
+            RenameRule::ScreamingKebab => name.to_shouty_kebab_case(),
+        }
+    }
+}
+
+/// A value written as-is into the schema. Unlike `syn::Expr`, whose
+/// `FromMeta` parses string literals as expressions, `"en"` stays a string.
+#[derive(Debug)]
+struct SchemaValue(syn::Expr);
+
+impl FromMeta for SchemaValue {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        Ok(Self(expr.clone()))
+    }
+}
+
+impl ToTokens for SchemaValue {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens);
+    }
+}
+
+/// `examples = ["a", "b"]`.
+#[derive(Debug)]
+struct Examples(syn::ExprArray);
+
+impl FromMeta for Examples {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Array(array) => Ok(Self(array.clone())),
+            _ => Err(darling::Error::custom("expected an array of examples").with_span(expr)),
+        }
+    }
+}
+
+/// The `///` comments among `attrs`, one line per comment line.
+pub(crate) fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(line),
+                        ..
+                    }),
+                ..
+            }) => Some(line.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn unsupported_type(ty: &syn::Type) -> Option<syn::Error> {
+    let kind = match ty {
+        syn::Type::BareFn(_) => "function pointers",
+        syn::Type::Ptr(_) => "raw pointers",
+        syn::Type::TraitObject(_) => "trait objects",
+        syn::Type::ImplTrait(_) => "`impl Trait` types",
+        syn::Type::Never(_) => "the never type",
+        _ => return None,
+    };
+    Some(syn::Error::new_spanned(
+        ty,
+        format!("{kind} have no JSON schema, use a field type that implements `Jsonify`"),
+    ))
+}
 
 #[derive(Debug, FromField)]
-#[darling(attributes(description))]
+#[darling(attributes(description, object), forward_attrs(serde, doc))]
 struct StructField {
     ident: Option<syn::Ident>,
     ty: syn::Type,
+    attrs: Vec<syn::Attribute>,
+    /// Property name, taking precedence over serde's and `rename_all`.
+    rename: Option<String>,
     #[darling(default)]
     description: Option<String>,
+    title: Option<String>,
+    #[darling(default)]
+    deprecated: bool,
+    #[darling(default)]
+    sensitive: bool,
+    /// Properties with a lower order come first, unordered ones keep their
+    /// declaration order after all ordered ones.
+    order: Option<i64>,
+    minimum: Option<SchemaValue>,
+    maximum: Option<SchemaValue>,
+    exclusive_minimum: Option<SchemaValue>,
+    exclusive_maximum: Option<SchemaValue>,
+    pattern: Option<String>,
+    min_length: Option<u64>,
+    max_length: Option<u64>,
+    format: Option<String>,
+    min_items: Option<u64>,
+    max_items: Option<u64>,
+    #[darling(default)]
+    unique_items: bool,
+    /// Fields with a default are never required.
+    default: Option<SchemaValue>,
+    examples: Option<Examples>,
+    /// `const` is a keyword and cannot name an attribute argument.
+    constant: Option<SchemaValue>,
+    /// A `fn() -> serde_json::Value` producing the schema instead of the
+    /// field type, which then need not implement `Jsonify`.
+    schema_with: Option<syn::Path>,
+    /// Merge the properties of the field's object schema into the parent,
+    /// like `#[serde(flatten)]`.
+    #[darling(default)]
+    flatten: bool,
+    /// Leave the field out of the schema, like `#[serde(skip)]`.
+    #[darling(default)]
+    skip: bool,
+    /// Shorthand for `required = false`.
+    #[darling(default)]
+    optional: bool,
+    /// Overrides whether the property is required, which otherwise follows
+    /// the field type and defaults.
+    required: Option<bool>,
+}
+
+fn ordered<'a>(fields: impl IntoIterator<Item = &'a StructField>) -> Vec<&'a StructField> {
+    let mut fields = fields.into_iter().collect::<Vec<_>>();
+    fields.sort_by_key(|f| (f.order.is_none(), f.order));
+    fields
+}
+
+/// Statements filling the `properties` object and `required` list of an
+/// object schema from `fields`, after the `leading` properties.
+fn properties(
+    fields: Vec<&StructField>,
+    container: &ObjectReceiver,
+    leading: TokenStream,
+    name: impl Fn(&StructField) -> String,
+) -> TokenStream {
+    let container_default = container.serde().default;
+    let (flattened, fields): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .map(|f| (f, f.serde()))
+        .filter(|(f, serde)| !f.skip && !serde.skip)
+        .partition(|(f, serde)| f.flatten || serde.flatten);
+    let (properties, required): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .map(|(f, serde)| {
+            let name = f
+                .rename
+                .clone()
+                .or_else(|| serde.rename.clone())
+                .unwrap_or_else(|| name(f));
+            let ty = &f.ty;
+            let optional = f.default.is_some() || serde.default || container_default;
+            let optional = match f.required {
+                Some(required) => !required,
+                None => f.optional || optional,
+            };
+            let required = match optional {
+                _ if container.strict || f.required == Some(true) => {
+                    quote!(required.push(#name.to_string());)
+                }
+                true => quote!(),
+                false if f.schema_with.is_some() => quote!(required.push(#name.to_string());),
+                false => quote! {
+                    if !<#ty as ::ai_tools_ox::Jsonify>::OPTIONAL {
+                        required.push(#name.to_string());
+                    }
+                },
+            };
+            // Strict mode lists every property as required, formerly
+            // optional ones accept `null` instead.
+            let nullable = match optional {
+                _ if !container.strict || f.required == Some(true) => None,
+                true => Some(quote!(true)),
+                false if f.schema_with.is_some() => None,
+                false => Some(quote!(<#ty as ::ai_tools_ox::Jsonify>::OPTIONAL)),
+            };
+            (f.property(&name, nullable), required)
+        })
+        .unzip();
+    let flattened = flattened.into_iter().map(|(f, _)| {
+        let ty = &f.ty;
+        let (schema, optional) = match &f.schema_with {
+            Some(path) => (quote!(#path()), quote!(false)),
+            None => (
+                quote!(<#ty as ::ai_tools_ox::Jsonify>::jsonify_with(config)),
+                quote!(<#ty as ::ai_tools_ox::Jsonify>::OPTIONAL),
+            ),
+        };
+        quote! {
+            config.flatten(&mut properties, &mut required, #schema, #optional);
+        }
+    });
+    quote! {
+        #(#required)*
+        #[allow(unused_mut)]
+        let mut properties = ::ai_tools_ox::__private::serde_json::json!({ #leading #(#properties),* });
+        #(#flattened)*
+    }
+}
+
+impl StructField {
+    fn serde(&self) -> SerdeAttrs {
+        SerdeAttrs::from_attrs(&self.attrs)
+    }
+
+    fn property(&self, name: &str, nullable: Option<TokenStream>) -> TokenStream {
+        let schema = self.type_schema();
+        let schema = match nullable {
+            Some(nullable) => self.with_keywords(quote! {
+                match #nullable {
+                    true => config.or_null(#schema),
+                    false => #schema,
+                }
+            }),
+            None => self.with_keywords(schema),
+        };
+        quote!(#name: #schema)
+    }
+
+    /// The field type's schema with the field's keywords applied.
+    fn schema(&self) -> TokenStream {
+        self.with_keywords(self.type_schema())
+    }
+
+    fn type_schema(&self) -> TokenStream {
+        let ty = &self.ty;
+        match &self.schema_with {
+            Some(path) => quote_spanned!(path.span()=> #path()),
+            None => {
+                quote_spanned!(ty.span()=> <#ty as ::ai_tools_ox::Jsonify>::jsonify_with(config))
+            }
+        }
+    }
+
+    fn with_keywords(&self, schema: TokenStream) -> TokenStream {
+        let mut keywords = Vec::new();
+        if let Some(title) = &self.title {
+            keywords.push(quote!("title": #title));
+        }
+        if let Some(description) = self
+            .description
+            .clone()
+            .or_else(|| doc_comment(&self.attrs))
+        {
+            keywords.push(quote!("description": #description));
+        }
+        if self.deprecated {
+            keywords.push(quote!("deprecated": true));
+        }
+        if self.sensitive {
+            keywords.push(quote!("x-sensitive": true));
+        }
+        for (keyword, bound) in [
+            ("minimum", &self.minimum),
+            ("maximum", &self.maximum),
+            ("exclusiveMinimum", &self.exclusive_minimum),
+            ("exclusiveMaximum", &self.exclusive_maximum),
+        ] {
+            if let Some(bound) = bound {
+                keywords.push(quote!(#keyword: #bound));
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            keywords.push(quote!("pattern": #pattern));
+        }
+        for (keyword, length) in [
+            ("minLength", self.min_length),
+            ("maxLength", self.max_length),
+            ("minItems", self.min_items),
+            ("maxItems", self.max_items),
+        ] {
+            if let Some(length) = length {
+                keywords.push(quote!(#keyword: #length));
+            }
+        }
+        if let Some(format) = &self.format {
+            keywords.push(quote!("format": #format));
+        }
+        if self.unique_items {
+            keywords.push(quote!("uniqueItems": true));
+        }
+        if let Some(default) = &self.default {
+            keywords.push(quote!("default": #default));
+        }
+        if let Some(constant) = &self.constant {
+            keywords.push(quote!("const": #constant));
+        }
+        if let Some(Examples(examples)) = &self.examples {
+            keywords.push(quote!("examples": #examples));
+        }
+        quote! {
+            config.property(
+                #schema,
+                ::ai_tools_ox::__private::serde_json::json!({ #(#keywords),* }),
+            )
+        }
+    }
+}
+
+/// How enum variants are told apart, mirroring serde's enum representations.
+/// Without any, variants are externally tagged like serde does.
+enum EnumRepr {
+    External,
+    Internal { tag: String },
+    Adjacent { tag: String, content: String },
+    Untagged,
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(description, object), forward_attrs(serde))]
+struct EnumVariant {
+    ident: syn::Ident,
+    attrs: Vec<syn::Attribute>,
+    fields: ast::Fields<StructField>,
+    rename: Option<String>,
+}
+
+/// `#[tool(name = "...", description = "...")]` on an argument type.
+#[derive(Debug, FromMeta)]
+struct ToolDefinition {
+    /// Defaults to the type name in snake case.
+    name: Option<String>,
+    /// Defaults to the type's description.
+    description: Option<String>,
 }
 
 #[derive(Debug, FromDeriveInput)]
-#[darling(attributes(description), supports(struct_any))]
+#[darling(
+    attributes(description, object),
+    supports(struct_any, enum_any),
+    forward_attrs(serde, doc, tool)
+)]
 struct ObjectReceiver {
     ident: syn::Ident,
     generics: syn::Generics,
-    data: ast::Data<(), StructField>,
+    attrs: Vec<syn::Attribute>,
+    data: ast::Data<EnumVariant, StructField>,
+    description: Option<String>,
+    rename_all: Option<RenameRule>,
+    /// serde's `rename_all`, used when the type sets no `rename_all` itself.
+    #[darling(skip)]
+    serde_rename_all: Option<SerdeCase>,
+    /// Name of the discriminator property of an internally or adjacently
+    /// tagged enum.
+    tag: Option<String>,
+    /// Name of the property holding the variant data of an adjacently
+    /// tagged enum.
+    content: Option<String>,
+    #[darling(default)]
+    untagged: bool,
+    /// Wrap every variant in an object keyed by its name. Already the
+    /// default, accepted to be explicit.
+    #[darling(default)]
+    #[allow(dead_code)]
+    external: bool,
+    /// Require every property and reject unknown ones.
+    #[darling(default)]
+    strict: bool,
+    /// Where predicates replacing the inferred `T: Jsonify` bounds, e.g.
+    /// `bound = "T: Jsonify + Clone"` or `bound = ""`.
+    bound: Option<String>,
+    /// Newtype structs take the schema of their field unless this is
+    /// `false`, which makes them a one-element array.
+    transparent: Option<bool>,
+    /// Unit structs are an empty object unless this is set, which makes
+    /// them `null` like serde serializes them.
+    #[darling(default)]
+    null: bool,
+}
+
+impl ObjectReceiver {
+    fn serde(&self) -> SerdeAttrs {
+        SerdeAttrs::from_attrs(&self.attrs)
+    }
+
+    fn parse(input: &DeriveInput) -> darling::Result<Self> {
+        let mut receiver = Self::from_derive_input(input)?;
+        receiver.serde_rename_all = receiver
+            .serde()
+            .rename_all
+            .as_ref()
+            .map(SerdeCase::from_lit)
+            .transpose()?;
+        Ok(receiver)
+    }
+
+    fn rename_field(&self, name: String) -> String {
+        match (self.rename_all, self.serde_rename_all) {
+            (Some(rule), _) => rule.apply(&name),
+            (None, Some(case)) => case.apply_to_field(&name),
+            (None, None) => name,
+        }
+    }
+
+    fn rename_variant(&self, name: String) -> String {
+        match (self.rename_all, self.serde_rename_all) {
+            (Some(rule), _) => rule.apply(&name),
+            (None, Some(case)) => case.apply_to_variant(&name),
+            (None, None) => name,
+        }
+    }
+
+    /// The generics of the impl, with a `Jsonify` bound on every type
+    /// parameter unless `bound` overrides them.
+    fn bounded_generics(&self) -> Result<syn::Generics, syn::Error> {
+        let mut generics = self.generics.clone();
+        let predicates = match &self.bound {
+            Some(bound) if bound.trim().is_empty() => Vec::new(),
+            Some(bound) => syn::parse_str::<syn::WhereClause>(&format!("where {bound}"))
+                .map_err(|e| syn::Error::new(self.ident.span(), format!("invalid bound: {e}")))?
+                .predicates
+                .into_iter()
+                .collect(),
+            None => self
+                .generics
+                .type_params()
+                .map(|param| {
+                    let ident = &param.ident;
+                    syn::parse_quote!(#ident: ::ai_tools_ox::Jsonify)
+                })
+                .collect(),
+        };
+        generics.make_where_clause().predicates.extend(predicates);
+        Ok(generics)
+    }
+
+    fn repr(&self) -> EnumRepr {
+        let serde = self.serde();
+        if self.untagged || serde.untagged {
+            return EnumRepr::Untagged;
+        }
+        let tag = self.tag.clone().or(serde.tag);
+        let content = self.content.clone().or(serde.content);
+        match (tag, content) {
+            (Some(tag), Some(content)) => EnumRepr::Adjacent { tag, content },
+            (Some(tag), None) => EnumRepr::Internal { tag },
+            (None, _) => EnumRepr::External,
+        }
+    }
+
+    fn struct_schema(&self, fields: &ast::Fields<&StructField>) -> Result<TokenStream, syn::Error> {
+        match (fields.style, &fields.fields[..]) {
+            (ast::Style::Unit, _) if self.null => {
+                return Ok(quote!(
+                    ::ai_tools_ox::__private::serde_json::json!({ "type": "null" })
+                ));
+            }
+            (ast::Style::Unit, _) => {}
+            (ast::Style::Tuple, [field]) if self.transparent != Some(false) => {
+                return Ok(field.schema());
+            }
+            (ast::Style::Tuple, [field]) => {
+                let schema = field.schema();
+                return Ok(quote!(config.tuple(vec![#schema])));
+            }
+            // Positional like serde's tuple structs.
+            (ast::Style::Tuple, fields) => {
+                let schemas = fields
+                    .iter()
+                    .filter(|f| !f.skip && !f.serde().skip)
+                    .map(|f| f.schema());
+                return Ok(quote!(config.tuple(vec![#(#schemas),*])));
+            }
+            _ => {}
+        }
+        let fields = &fields.fields;
+        let properties = properties(ordered(fields.iter().copied()), self, quote!(), |f| {
+            self.rename_field(f.ident.as_ref().unwrap().to_string())
+        });
+        let object = quote!(config.object(properties, required));
+        let object = match self.strict {
+            true => quote!(config.closed(#object)),
+            false => object,
+        };
+        Ok(quote! {{
+            #[allow(unused_mut)]
+            let mut required: Vec<String> = Vec::new();
+            #properties
+            #object
+        }})
+    }
+
+    fn variant_name(&self, variant: &EnumVariant) -> String {
+        variant
+            .rename
+            .clone()
+            .or_else(|| variant.serde().rename)
+            .unwrap_or_else(|| self.rename_variant(variant.ident.to_string()))
+    }
+
+    /// Enums without data and without an explicit representation are plain
+    /// strings.
+    fn unit_enum_schema(&self, variants: &[&EnumVariant]) -> TokenStream {
+        let names = variants.iter().map(|v| self.variant_name(v));
+        quote! {
+            ::ai_tools_ox::__private::serde_json::json!({ "type": "string", "enum": [#(#names),*] })
+        }
+    }
+
+    /// An object of the variant's fields, after the `tag` property if given.
+    fn variant_object(&self, variant: &EnumVariant, tag: Option<&str>) -> TokenStream {
+        let (leading, required) = match tag {
+            Some(tag) => {
+                let value = self.variant_name(variant);
+                (
+                    quote!(#tag: { "type": "string", "const": #value },),
+                    quote!(vec![#tag.to_string()]),
+                )
+            }
+            None => (quote!(), quote!(Vec::new())),
+        };
+        let properties = properties(ordered(variant.fields.iter()), self, leading, |f| {
+            f.ident.as_ref().unwrap().to_string()
+        });
+        let closed = self.strict.then(|| quote!("additionalProperties": false,));
+        quote! {{
+            #[allow(unused_mut)]
+            let mut required: Vec<String> = #required;
+            #properties
+            ::ai_tools_ox::__private::serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                #closed
+            })
+        }}
+    }
+
+    /// The variant data as serde writes it without a tag.
+    fn variant_content(&self, variant: &EnumVariant) -> TokenStream {
+        match (variant.fields.style, &variant.fields.fields[..]) {
+            (ast::Style::Unit, _) => {
+                quote!(::ai_tools_ox::__private::serde_json::json!({ "type": "null" }))
+            }
+            (ast::Style::Tuple, [field]) => field.schema(),
+            (ast::Style::Tuple, fields) => {
+                let schemas = fields.iter().map(|f| f.schema());
+                quote!(config.tuple(vec![#(#schemas),*]))
+            }
+            (ast::Style::Struct, _) => self.variant_object(variant, None),
+        }
+    }
+
+    /// Every variant is an object whose `tag` property holds the variant
+    /// name.
+    fn internal_variant(
+        &self,
+        variant: &EnumVariant,
+        tag: &str,
+    ) -> Result<TokenStream, syn::Error> {
+        if variant.fields.style != ast::Style::Tuple {
+            return Ok(self.variant_object(variant, Some(tag)));
+        }
+        // Like serde, a newtype variant merges the tag into the inner object.
+        let [field] = &variant.fields.fields[..] else {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                "tuple variants cannot be represented in an internally tagged enum",
+            ));
+        };
+        let value = self.variant_name(variant);
+        let schema = field.schema();
+        Ok(quote! {
+            ::ai_tools_ox::__private::serde_json::json!({
+                "allOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            #tag: { "type": "string", "const": #value },
+                        },
+                        "required": [#tag],
+                    },
+                    #schema,
+                ]
+            })
+        })
+    }
+
+    /// Unit variants are their name, the others an object with the name as
+    /// its only property.
+    fn external_variant(&self, variant: &EnumVariant) -> TokenStream {
+        let value = self.variant_name(variant);
+        if variant.fields.is_unit() {
+            return quote!(
+                ::ai_tools_ox::__private::serde_json::json!({ "type": "string", "const": #value })
+            );
+        }
+        let content = self.variant_content(variant);
+        quote! {{
+            let content: ::ai_tools_ox::__private::serde_json::Value = #content;
+            ::ai_tools_ox::__private::serde_json::json!({
+                "type": "object",
+                "properties": { #value: content },
+                "required": [#value],
+                "additionalProperties": false,
+            })
+        }}
+    }
+
+    /// The name under `tag` and the data, if any, under `content`.
+    fn adjacent_variant(&self, variant: &EnumVariant, tag: &str, content: &str) -> TokenStream {
+        let value = self.variant_name(variant);
+        let tag_schema = quote!({ "type": "string", "const": #value });
+        if variant.fields.is_unit() {
+            return quote! {
+                ::ai_tools_ox::__private::serde_json::json!({
+                    "type": "object",
+                    "properties": { #tag: #tag_schema },
+                    "required": [#tag],
+                })
+            };
+        }
+        let data = self.variant_content(variant);
+        quote! {{
+            let data: ::ai_tools_ox::__private::serde_json::Value = #data;
+            ::ai_tools_ox::__private::serde_json::json!({
+                "type": "object",
+                "properties": { #tag: #tag_schema, #content: data },
+                "required": [#tag, #content],
+            })
+        }}
+    }
+
+    fn enum_schema(&self, variants: &[&EnumVariant]) -> Result<TokenStream, syn::Error> {
+        let variants = variants
+            .iter()
+            .filter(|v| !v.serde().skip)
+            .copied()
+            .collect::<Vec<_>>();
+        let variants = &variants[..];
+        let repr = self.repr();
+        let unit = variants.iter().all(|v| v.fields.is_unit());
+        if unit && matches!(repr, EnumRepr::External) {
+            return Ok(self.unit_enum_schema(variants));
+        }
+        let variants = variants
+            .iter()
+            .map(|v| match &repr {
+                EnumRepr::External => Ok(self.external_variant(v)),
+                EnumRepr::Internal { tag } => self.internal_variant(v, tag),
+                EnumRepr::Adjacent { tag, content } => Ok(self.adjacent_variant(v, tag, content)),
+                EnumRepr::Untagged => Ok(self.variant_content(v)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Untagged variants may overlap, so only one of them need match.
+        let combinator = match repr {
+            EnumRepr::Untagged => "anyOf",
+            _ => "oneOf",
+        };
+        Ok(quote! {{
+            let variants: Vec<::ai_tools_ox::__private::serde_json::Value> = vec![#(#variants),*];
+            ::ai_tools_ox::__private::serde_json::json!({ #combinator: variants })
+        }})
+    }
+}
+
+impl ObjectReceiver {
+    /// An associated `tool_definition()` using the fields as parameters.
+    fn tool_definition(
+        &self,
+        definition: ToolDefinition,
+        generics: &syn::Generics,
+    ) -> darling::Result<TokenStream> {
+        let ident = &self.ident;
+        let name = definition
+            .name
+            .unwrap_or_else(|| ident.to_string().to_snake_case());
+        let description = definition
+            .description
+            .or_else(|| self.description.clone())
+            .or_else(|| doc_comment(&self.attrs))
+            .ok_or_else(|| {
+                darling::Error::custom(
+                    "tools need a description, add a doc comment or `description = \"...\"`",
+                )
+                .with_span(ident)
+            })?;
+        check_definition(&name, &description, ident)?;
+        let (imp, ty, wher) = generics.split_for_impl();
+        Ok(quote! {
+            impl #imp #ident #ty #wher {
+                pub fn tool_definition() -> ::ai_tools_ox::tools::Tool {
+                    ::ai_tools_ox::tools::ToolBuilder::new()
+                        .name(#name)
+                        .description(#description)
+                        .add_struct_parameters::<Self>()
+                        .build()
+                        .expect("tool definition is complete")
+                }
+            }
+        })
+    }
+}
+
+impl EnumVariant {
+    fn serde(&self) -> SerdeAttrs {
+        SerdeAttrs::from_attrs(&self.attrs)
+    }
 }
 
 impl ToTokens for ObjectReceiver {
@@ -29,54 +753,121 @@ impl ToTokens for ObjectReceiver {
             ..
         } = *self;
 
-        let (imp, ty, wher) = generics.split_for_impl();
+        let bounded = match self.bounded_generics() {
+            Ok(bounded) => bounded,
+            Err(e) => {
+                tokens.extend(e.to_compile_error());
+                return;
+            }
+        };
+        let (imp, ty, wher) = bounded.split_for_impl();
 
-        let extracted_fields = data
-            .as_ref()
-            .take_struct()
-            .unwrap()
-            .fields
-            .iter()
-            .map(|f| {
-                let name = f.ident.as_ref().unwrap();
-                let ty = &f.ty;
-                if let Some(description) = &f.description {
-                    quote! {
-                        stringify!(#name): serde_json::json!({
-                            "type": <#ty as Jsonify>::jsonify(),
-                            "description": #description
-                        })
-                    }
-                } else {
-                    quote! {
-                        stringify!(#name): serde_json::json!({
-                            "type": <#ty as Jsonify>::jsonify(),
-                        })
-                    }
-                }
-            })
-            .collect::<Vec<_>>();
+        let errors = match data {
+            ast::Data::Struct(fields) => fields.iter().collect::<Vec<_>>(),
+            ast::Data::Enum(variants) => variants
+                .iter()
+                .filter(|v| !v.serde().skip)
+                .flat_map(|v| v.fields.iter())
+                .collect(),
+        }
+        .into_iter()
+        .filter(|f| !f.skip && !f.serde().skip && f.schema_with.is_none())
+        .filter_map(|f| unsupported_type(&f.ty))
+        .map(|e| e.to_compile_error())
+        .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            tokens.extend(errors);
+            return;
+        }
+
+        let schema = match data.as_ref() {
+            ast::Data::Struct(fields) => self.struct_schema(&fields),
+            ast::Data::Enum(variants) => self.enum_schema(&variants),
+        };
+        let schema = match schema {
+            Ok(schema) => schema,
+            Err(e) => {
+                tokens.extend(e.to_compile_error());
+                return;
+            }
+        };
+
+        let schema = match self
+            .description
+            .clone()
+            .or_else(|| doc_comment(&self.attrs))
+        {
+            Some(description) => quote! {
+                config.constrained(#schema, ::ai_tools_ox::__private::serde_json::json!({ "description": #description }))
+            },
+            None => schema,
+        };
+
+        // Generic types have no single name to put under `$defs`, recursion
+        // through them is not supported.
+        let schema = match generics.params.is_empty() {
+            true => quote!(config.named(::std::any::type_name::<Self>(), || #schema)),
+            false => schema,
+        };
 
         tokens.extend(quote! {
-            impl #imp Jsonify for #ident #ty #wher {
-                fn jsonify() -> serde_json::Value {
-                    serde_json::json!({ #(#extracted_fields),* })
+            impl #imp ::ai_tools_ox::Jsonify for #ident #ty #wher {
+                fn jsonify_with(config: &::ai_tools_ox::JsonifyConfig) -> ::ai_tools_ox::__private::serde_json::Value {
+                    #schema
                 }
             }
         });
+        if let Some(attr) = self.attrs.iter().find(|attr| attr.path().is_ident("tool")) {
+            let definition = ToolDefinition::from_meta(&attr.meta)
+                .map_err(darling::Error::from)
+                .and_then(|definition| self.tool_definition(definition, &bounded));
+            tokens.extend(definition.unwrap_or_else(|e| e.write_errors()));
+        }
     }
 }
 
 pub fn expand(input: &DeriveInput) -> TokenStream {
-    match ObjectReceiver::from_derive_input(input) {
-        Ok(receiver) => {
-            let tokens = quote!(#receiver);
-            dbg!(&tokens);
-            tokens
-        }
-        Err(e) => {
-            // Możesz obsłużyć błąd w bardziej złożony sposób, np. zwracając TokenStream z błędem
-            panic!("Error parsing derive input: {}", e);
+    let tokens = match ObjectReceiver::parse(input) {
+        Ok(receiver) => quote!(#receiver),
+        Err(e) => e.write_errors(),
+    };
+    // Set `AI_TOOLS_OX_DEBUG` to print the generated impls while building.
+    if std::env::var_os("AI_TOOLS_OX_DEBUG").is_some() {
+        eprintln!("{tokens}");
+    }
+    tokens
+}
+
+/// `EnumJsonify` for enums without data, naming the variants like the
+/// `Object` derive does.
+pub fn expand_enum(input: &DeriveInput) -> TokenStream {
+    let receiver = match ObjectReceiver::parse(input) {
+        Ok(receiver) => receiver,
+        Err(e) => return e.write_errors(),
+    };
+    let ast::Data::Enum(variants) = &receiver.data else {
+        return syn::Error::new_spanned(
+            &receiver.ident,
+            "`EnumJsonify` can only be derived for enums",
+        )
+        .to_compile_error();
+    };
+    let mut names = Vec::new();
+    for variant in variants.iter().filter(|v| !v.serde().skip) {
+        if !variant.fields.is_unit() {
+            return syn::Error::new_spanned(
+                &variant.ident,
+                "`EnumJsonify` variants cannot have fields",
+            )
+            .to_compile_error();
+        }
+        names.push(receiver.variant_name(variant));
+    }
+    let ident = &receiver.ident;
+    let (imp, ty, wher) = receiver.generics.split_for_impl();
+    quote! {
+        impl #imp ::ai_tools_ox::EnumJsonify for #ident #ty #wher {
+            const VARIANTS: &'static [&'static str] = &[#(#names),*];
         }
     }
 }
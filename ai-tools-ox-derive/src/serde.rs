@@ -0,0 +1,183 @@
+use syn::{meta::ParseNestedMeta, Attribute, LitStr, Token};
+
+/// The `#[serde(...)]` arguments that change what a type deserializes from.
+/// Malformed attributes are left for serde's own derive to report.
+#[derive(Debug, Default)]
+pub struct SerdeAttrs {
+    pub rename: Option<String>,
+    pub rename_all: Option<LitStr>,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+    pub skip: bool,
+    pub default: bool,
+    /// The function of `default = "path"`.
+    pub default_fn: Option<syn::ExprPath>,
+    pub flatten: bool,
+}
+
+impl SerdeAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> Self {
+        let mut serde = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            let _ = attr.parse_nested_meta(|meta| serde.parse(meta));
+        }
+        serde
+    }
+
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        let path = &meta.path;
+        if path.is_ident("rename") {
+            self.rename = deserialize_name(&meta)?;
+        } else if path.is_ident("rename_all") {
+            self.rename_all = deserialize_lit(&meta)?;
+        } else if path.is_ident("tag") {
+            self.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+        } else if path.is_ident("content") {
+            self.content = Some(meta.value()?.parse::<LitStr>()?.value());
+        } else if path.is_ident("untagged") {
+            self.untagged = true;
+        } else if path.is_ident("skip") || path.is_ident("skip_deserializing") {
+            self.skip = true;
+        } else if path.is_ident("flatten") {
+            self.flatten = true;
+        } else if path.is_ident("default") {
+            self.default = true;
+            if meta.input.peek(Token![=]) {
+                self.default_fn = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            }
+        } else {
+            skip_value(&meta)?;
+        }
+        Ok(())
+    }
+}
+
+/// `rename = "a"` or `rename(deserialize = "a")`.
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    Ok(deserialize_lit(meta)?.map(|lit| lit.value()))
+}
+
+fn deserialize_lit(meta: &ParseNestedMeta) -> syn::Result<Option<LitStr>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+    let mut lit = None;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("deserialize") {
+            lit = Some(meta.value()?.parse()?);
+        } else {
+            skip_value(&meta)?;
+        }
+        Ok(())
+    })?;
+    Ok(lit)
+}
+
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|meta| skip_value(&meta))?;
+    }
+    Ok(())
+}
+
+/// serde's `rename_all` rules, converting names exactly like serde does so
+/// that the schema matches what deserializes. Unlike heck, acronyms and
+/// digits are not treated as words, e.g. `HTTPServer` is `h_t_t_p_server`.
+#[derive(Debug, Clone, Copy)]
+pub enum SerdeCase {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl SerdeCase {
+    const RULES: [(&'static str, SerdeCase); 8] = [
+        ("lowercase", SerdeCase::Lower),
+        ("UPPERCASE", SerdeCase::Upper),
+        ("PascalCase", SerdeCase::Pascal),
+        ("camelCase", SerdeCase::Camel),
+        ("snake_case", SerdeCase::Snake),
+        ("SCREAMING_SNAKE_CASE", SerdeCase::ScreamingSnake),
+        ("kebab-case", SerdeCase::Kebab),
+        ("SCREAMING-KEBAB-CASE", SerdeCase::ScreamingKebab),
+    ];
+
+    pub fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        let rule = lit.value();
+        Self::RULES
+            .iter()
+            .find(|(name, _)| *name == rule)
+            .map(|(_, case)| *case)
+            .ok_or_else(|| {
+                let expected = Self::RULES.map(|(name, _)| format!("{name:?}")).join(", ");
+                syn::Error::new(
+                    lit.span(),
+                    format!("unknown rename rule {rule:?}, expected one of {expected}"),
+                )
+            })
+    }
+
+    /// Renames a field, which is expected to be in snake_case.
+    pub fn apply_to_field(self, field: &str) -> String {
+        match self {
+            SerdeCase::Lower | SerdeCase::Snake => field.to_string(),
+            SerdeCase::Upper | SerdeCase::ScreamingSnake => field.to_ascii_uppercase(),
+            SerdeCase::Pascal => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            SerdeCase::Camel => {
+                let pascal = SerdeCase::Pascal.apply_to_field(field);
+                pascal[..1].to_ascii_lowercase() + &pascal[1..]
+            }
+            SerdeCase::Kebab => field.replace('_', "-"),
+            SerdeCase::ScreamingKebab => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+
+    /// Renames a variant, which is expected to be in PascalCase.
+    pub fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            SerdeCase::Pascal => variant.to_string(),
+            SerdeCase::Lower => variant.to_ascii_lowercase(),
+            SerdeCase::Upper => variant.to_ascii_uppercase(),
+            SerdeCase::Camel => variant[..1].to_ascii_lowercase() + &variant[1..],
+            SerdeCase::Snake => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            SerdeCase::ScreamingSnake => SerdeCase::Snake
+                .apply_to_variant(variant)
+                .to_ascii_uppercase(),
+            SerdeCase::Kebab => SerdeCase::Snake.apply_to_variant(variant).replace('_', "-"),
+            SerdeCase::ScreamingKebab => SerdeCase::ScreamingSnake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use darling::{ast::NestedMeta, FromDeriveInput, FromMeta};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+
+use crate::object::doc_comment;
+
+#[derive(Debug, Default, FromMeta)]
+struct ToolArgs {
+    /// Defaults to the function name.
+    name: Option<String>,
+    /// Defaults to the doc comment.
+    description: Option<String>,
+}
+
+/// Limits enforced by `ToolBuilder::build`, checked at expansion time so an
+/// invalid definition fails to compile rather than panicking in `to_tool`.
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+
+pub(crate) fn check_definition(
+    name: &str,
+    description: &str,
+    span: &impl syn::spanned::Spanned,
+) -> darling::Result<()> {
+    let valid_name = (1..=MAX_NAME_LENGTH).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        return Err(darling::Error::custom(format!(
+            "invalid tool name {name:?}: expected 1 to {MAX_NAME_LENGTH} of a-z, A-Z, 0-9, _ and -"
+        ))
+        .with_span(span));
+    }
+    let length = description.chars().count();
+    if length > MAX_DESCRIPTION_LENGTH {
+        return Err(darling::Error::custom(format!(
+            "description is {length} characters long, at most {MAX_DESCRIPTION_LENGTH} are allowed"
+        ))
+        .with_span(span));
+    }
+    Ok(())
+}
+
+/// A tool parameter taken from the function signature.
+struct Param {
+    ident: syn::Ident,
+    ty: syn::Type,
+    description: String,
+}
+
+/// Splits a doc comment into the tool description and the descriptions of
+/// its arguments, listed rustdoc style as ``* `name` - description``.
+fn split_doc(doc: &str) -> (String, Vec<(String, String)>) {
+    let mut description = Vec::new();
+    let mut arguments = Vec::new();
+    let mut in_arguments = false;
+    for line in doc.lines() {
+        let item = line
+            .strip_prefix("* `")
+            .or_else(|| line.strip_prefix("- `"))
+            .and_then(|item| item.split_once('`'));
+        match item {
+            Some((name, text)) => {
+                let text = text.trim_start_matches([' ', '-', ':']).trim();
+                arguments.push((name.to_string(), text.to_string()));
+                in_arguments = true;
+            }
+            None if line.trim_start_matches('#').trim() == "Arguments" => in_arguments = true,
+            None if !in_arguments => description.push(line),
+            None => {}
+        }
+    }
+    (description.join("\n").trim().to_string(), arguments)
+}
+
+/// The typed parameters of `sig`, a method's receiver is skipped.
+fn params(
+    sig: &syn::Signature,
+    described: &[(String, String)],
+    method: bool,
+) -> Result<Vec<Param>, syn::Error> {
+    sig.inputs
+        .iter()
+        .filter(|input| !(method && matches!(input, syn::FnArg::Receiver(_))))
+        .map(|input| {
+            let syn::FnArg::Typed(arg) = input else {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "`#[tool]` functions cannot take `self`, use `#[toolbox]` on the impl block",
+                ));
+            };
+            let syn::Pat::Ident(pat) = &*arg.pat else {
+                return Err(syn::Error::new_spanned(
+                    &arg.pat,
+                    "tool parameters must be plain identifiers",
+                ));
+            };
+            let name = pat.ident.unraw().to_string();
+            let description = described
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, d)| d.clone())
+                .unwrap_or_default();
+            Ok(Param {
+                ident: pat.ident.clone(),
+                ty: (*arg.ty).clone(),
+                description,
+            })
+        })
+        .collect()
+}
+
+/// Whether the function returns a `Result`, judged by the type's name.
+fn returns_result(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    }
+}
+
+/// The `ToTool` impl for `tool_ident` calling `callee` with the parameters
+/// of `sig`.
+fn tool_impl(
+    args: ToolArgs,
+    attrs: &[syn::Attribute],
+    sig: &syn::Signature,
+    tool_ident: &syn::Ident,
+    callee: TokenStream,
+    method: bool,
+) -> darling::Result<TokenStream> {
+    if !sig.generics.params.is_empty() {
+        return Err(
+            syn::Error::new_spanned(&sig.generics, "tool functions cannot be generic").into(),
+        );
+    }
+    let (doc, described) = split_doc(&doc_comment(attrs).unwrap_or_default());
+    let description = match args.description {
+        Some(description) => description,
+        None if !doc.is_empty() => doc,
+        None => {
+            return Err(darling::Error::custom(
+                "tools need a description, add a doc comment or `description = \"...\"`",
+            )
+            .with_span(&sig.ident))
+        }
+    };
+    let name = args.name.unwrap_or_else(|| sig.ident.unraw().to_string());
+    check_definition(&name, &description, &sig.ident)?;
+    let params = params(sig, &described, method)?;
+
+    let idents = params.iter().map(|p| &p.ident).collect::<Vec<_>>();
+    let types = params.iter().map(|p| &p.ty).collect::<Vec<_>>();
+    let names = params.iter().map(|p| p.ident.unraw().to_string());
+    let parameters = params.iter().map(
+        |Param {
+             ident,
+             ty,
+             description,
+         }| {
+            let name = ident.unraw().to_string();
+            quote!(.add_parameter::<#ty>(#name, #description))
+        },
+    );
+    let call = match sig.asyncness {
+        Some(_) => quote!(#callee(#(#idents),*).await),
+        None => quote!(#callee(#(#idents),*)),
+    };
+    let output = match returns_result(&sig.output) {
+        true => quote! {
+            let output = #call.map_err(|e| ::ai_tools_ox::tools::ToolError::Failed(e.to_string()))?;
+        },
+        false => quote!(let output = #call;),
+    };
+
+    Ok(quote! {
+        #[::ai_tools_ox::__private::async_trait]
+        impl ::ai_tools_ox::tools::ToTool for #tool_ident {
+            fn to_tool(&self) -> ::ai_tools_ox::tools::Tool {
+                ::ai_tools_ox::tools::ToolBuilder::new()
+                    .name(#name)
+                    .description(#description)
+                    #(#parameters)*
+                    .build()
+                    .expect("tool definition is complete")
+            }
+            async fn call_tool(
+                &self,
+                __tool_call_id: &str,
+                __input: ::ai_tools_ox::__private::serde_json::Value,
+            ) -> Result<::ai_tools_ox::tools::ToolCallResult, ::ai_tools_ox::tools::ToolError> {
+                #[allow(unused_mut, unused_variables)]
+                let mut args = ::ai_tools_ox::args::object(__input)?;
+                #(let #idents: #types = ::ai_tools_ox::args::field(&mut args, #names)?;)*
+                #output
+                ::ai_tools_ox::tools::ToolCallResult::from_output(__tool_call_id, &output)
+            }
+        }
+    })
+}
+
+fn tool_ident(function: &syn::Ident) -> syn::Ident {
+    format_ident!("{}Tool", function.unraw().to_string().to_upper_camel_case())
+}
+
+fn tool(args: TokenStream, function: syn::ItemFn) -> darling::Result<TokenStream> {
+    let args = ToolArgs::from_list(&NestedMeta::parse_meta_list(args)?)?;
+    let ident = &function.sig.ident;
+    let vis = &function.vis;
+    let tool_ident = tool_ident(ident);
+    let doc = format!("The [`{ident}`] tool.");
+    let imp = tool_impl(
+        args,
+        &function.attrs,
+        &function.sig,
+        &tool_ident,
+        quote!(#ident),
+        false,
+    )?;
+    Ok(quote! {
+        #function
+
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #tool_ident;
+
+        #imp
+    })
+}
+
+/// Removes the `#[tool]` attribute of `method`, returning its arguments.
+fn take_tool_attr(method: &mut syn::ImplItemFn) -> Option<syn::Attribute> {
+    let position = method
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("tool"))?;
+    Some(method.attrs.remove(position))
+}
+
+fn toolbox(mut block: syn::ItemImpl) -> darling::Result<TokenStream> {
+    if !block.generics.params.is_empty() || block.trait_.is_some() {
+        return Err(syn::Error::new_spanned(
+            &block.self_ty,
+            "`#[toolbox]` supports inherent impls of non-generic types",
+        )
+        .into());
+    }
+    let self_ty = block.self_ty.clone();
+    let mut errors = darling::Error::accumulator();
+    let mut tools = Vec::new();
+    for item in &mut block.items {
+        let syn::ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let Some(attr) = take_tool_attr(method) else {
+            continue;
+        };
+        let args = match &attr.meta {
+            syn::Meta::Path(_) => Ok(ToolArgs::default()),
+            meta => ToolArgs::from_meta(meta),
+        };
+        let Some(args) = errors.handle(args) else {
+            continue;
+        };
+        let ident = &method.sig.ident;
+        let tool_ident = tool_ident(ident);
+        let imp = tool_impl(
+            args,
+            &method.attrs,
+            &method.sig,
+            &tool_ident,
+            quote!(self.0.#ident),
+            true,
+        );
+        if let Some(imp) = errors.handle(imp) {
+            tools.push((tool_ident, imp));
+        }
+    }
+    errors.finish()?;
+
+    let definitions = tools.iter().map(|(tool_ident, imp)| {
+        let name = tool_ident.to_string();
+        quote! {
+            struct #tool_ident(::std::sync::Arc<#self_ty>);
+
+            impl ::std::fmt::Debug for #tool_ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(#name)
+                }
+            }
+
+            #imp
+        }
+    });
+    let tool_idents = tools.iter().map(|(tool_ident, _)| tool_ident);
+    Ok(quote! {
+        #block
+
+        impl #self_ty {
+            /// Every `#[tool]` method as a tool sharing this instance.
+            pub fn toolbox(self: ::std::sync::Arc<Self>) -> ::ai_tools_ox::tools::Tools {
+                #(#definitions)*
+                ::ai_tools_ox::tools::Tools::default()
+                    #(.add_tool(#tool_idents(self.clone())))*
+            }
+        }
+    })
+}
+
+pub fn expand_toolbox(block: syn::ItemImpl) -> TokenStream {
+    let fallback = block.to_token_stream();
+    match toolbox(block) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let mut tokens = fallback;
+            tokens.extend(e.write_errors());
+            tokens
+        }
+    }
+}
+
+/// `tool_from_fn!(path::to::get_weather)` evaluates to the `Tool` of the
+/// `GetWeatherTool` generated by `#[tool]` next to the function.
+pub fn expand_from_fn(mut path: syn::Path) -> TokenStream {
+    let Some(last) = path.segments.last_mut() else {
+        return syn::Error::new_spanned(&path, "expected the path of a `#[tool]` function")
+            .to_compile_error();
+    };
+    last.ident = tool_ident(&last.ident);
+    quote! {
+        ::ai_tools_ox::tools::ToTool::to_tool(&#path)
+    }
+}
+
+pub fn expand(args: TokenStream, function: syn::ItemFn) -> TokenStream {
+    let fallback = function.to_token_stream();
+    match tool(args, function) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            let mut tokens = fallback;
+            tokens.extend(e.write_errors());
+            tokens
+        }
+    }
+}
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(tool), forward_attrs(doc))]
+struct ToolReceiver {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    attrs: Vec<syn::Attribute>,
+    /// Defaults to the type name in snake case.
+    name: Option<String>,
+    /// Defaults to the doc comment.
+    description: Option<String>,
+    /// The `async fn(&self, args: Args) -> Result<Output, E>` called with
+    /// the arguments, `run` by default.
+    handler: Option<syn::Ident>,
+}
+
+fn derive(input: &syn::DeriveInput) -> darling::Result<TokenStream> {
+    let receiver = ToolReceiver::from_derive_input(input)?;
+    let ident = &receiver.ident;
+    let name = receiver
+        .name
+        .unwrap_or_else(|| ident.to_string().to_snake_case());
+    let description =
+        match receiver
+            .description
+            .or_else(|| doc_comment(&receiver.attrs))
+        {
+            Some(description) => description,
+            None => return Err(darling::Error::custom(
+                "tools need a description, add a doc comment or `#[tool(description = \"...\")]`",
+            )
+            .with_span(ident)),
+        };
+    check_definition(&name, &description, ident)?;
+    let handler = receiver.handler.unwrap_or_else(|| format_ident!("run"));
+    let (imp, ty, wher) = receiver.generics.split_for_impl();
+
+    // The argument type is inferred from the handler's signature through a
+    // closure that is never called.
+    Ok(quote! {
+        #[::ai_tools_ox::__private::async_trait]
+        impl #imp ::ai_tools_ox::tools::ToTool for #ident #ty #wher {
+            fn to_tool(&self) -> ::ai_tools_ox::tools::Tool {
+                fn parameters<A: ::ai_tools_ox::Jsonify>(
+                    _: impl FnOnce(A),
+                ) -> ::ai_tools_ox::schema::Schema {
+                    ::ai_tools_ox::schema::Schema::of::<A>()
+                }
+                let parameters = parameters(|args| {
+                    let _ = self.#handler(args);
+                });
+                ::ai_tools_ox::tools::ToolBuilder::new()
+                    .name(#name)
+                    .description(#description)
+                    .add_schema_parameters(parameters)
+                    .build()
+                    .expect("tool definition is complete")
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                input: ::ai_tools_ox::__private::serde_json::Value,
+            ) -> Result<::ai_tools_ox::tools::ToolCallResult, ::ai_tools_ox::tools::ToolError> {
+                let args = ::ai_tools_ox::args::FromToolArgs::from_args(input)?;
+                let output = self
+                    .#handler(args)
+                    .await
+                    .map_err(|e| ::ai_tools_ox::tools::ToolError::Failed(e.to_string()))?;
+                ::ai_tools_ox::tools::ToolCallResult::from_output(id, &output)
+            }
+        }
+    })
+}
+
+pub fn expand_derive(input: &syn::DeriveInput) -> TokenStream {
+    derive(input).unwrap_or_else(|e| e.write_errors())
+}
@@ -0,0 +1,104 @@
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::tools::ToolError;
+
+/// Why tool arguments could not be parsed, worded for the model to correct
+/// its call.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ArgsError {
+    #[error("expected an object of arguments, got {0}")]
+    NotAnObject(&'static str),
+    #[error("missing required argument `{0}`")]
+    Missing(String),
+    #[error("argument `{field}` is invalid: {message}")]
+    Invalid { field: String, message: String },
+}
+
+impl From<ArgsError> for ToolError {
+    fn from(e: ArgsError) -> Self {
+        ToolError::InvalidArguments(e.to_string())
+    }
+}
+
+/// Parses the arguments of a tool call, usually derived with
+/// `#[derive(FromToolArgs)]`.
+pub trait FromToolArgs: Sized {
+    fn from_args(value: serde_json::Value) -> Result<Self, ArgsError>;
+}
+
+/// The arguments as an object, `null` counting as no arguments.
+pub fn object(
+    value: serde_json::Value,
+) -> Result<serde_json::Map<String, serde_json::Value>, ArgsError> {
+    let kind = match value {
+        serde_json::Value::Object(args) => return Ok(args),
+        serde_json::Value::Null => return Ok(Default::default()),
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+    };
+    Err(ArgsError::NotAnObject(kind))
+}
+
+/// Takes the argument `name` out of `args`. A missing argument is only an
+/// error if `T` cannot be deserialized from `null`, as `Option`s can.
+pub fn field<T: DeserializeOwned>(
+    args: &mut serde_json::Map<String, serde_json::Value>,
+    name: &str,
+) -> Result<T, ArgsError> {
+    match args.remove(name) {
+        Some(value) => serde_json::from_value(value).map_err(|e| ArgsError::Invalid {
+            field: name.to_string(),
+            message: e.to_string(),
+        }),
+        None => serde_json::from_value(serde_json::Value::Null)
+            .map_err(|_| ArgsError::Missing(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(Debug, ai_tools_ox_derive::FromToolArgs)]
+    #[serde(rename_all = "camelCase")]
+    struct Transfer {
+        from_account: String,
+        amount: u64,
+        memo: Option<String>,
+        #[serde(default)]
+        priority: u8,
+        #[serde(skip)]
+        attempts: usize,
+    }
+
+    #[test]
+    fn test_from_args() {
+        let transfer =
+            Transfer::from_args(json!({"fromAccount": "a-1", "amount": 10, "memo": "rent"}))
+                .unwrap();
+        assert_eq!(transfer.from_account, "a-1");
+        assert_eq!(transfer.memo.as_deref(), Some("rent"));
+        assert_eq!(transfer.priority, 0);
+
+        assert_eq!(
+            Transfer::from_args(json!({"amount": 10})).unwrap_err(),
+            ArgsError::Missing("fromAccount".to_string())
+        );
+        assert_eq!(
+            Transfer::from_args(json!({"fromAccount": "a-1", "amount": "ten"}))
+                .unwrap_err()
+                .to_string(),
+            "argument `amount` is invalid: invalid type: string \"ten\", expected u64"
+        );
+        assert_eq!(
+            Transfer::from_args(json!("a-1")).unwrap_err(),
+            ArgsError::NotAnObject("a string")
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+
+/// What a tool call did, reported to an [`AuditSink`] once it finishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// When the call started, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub call_id: String,
+    pub tool: String,
+    /// The arguments with sensitive values redacted, or
+    /// [`crate::tools::REDACTED`] as a whole if they are not JSON, as the
+    /// parser can't tell which parts are sensitive.
+    pub arguments: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// The [`AuditContext`] of the call's [`crate::context::ToolContext`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+}
+
+/// Attached to the audit records of calls whose
+/// [`crate::context::ToolContext`] holds it, e.g. the user or session the
+/// calls run for.
+#[derive(Debug, Clone)]
+pub struct AuditContext(pub serde_json::Value);
+
+#[async_trait::async_trait]
+pub trait AuditSink: std::fmt::Debug + Send + Sync {
+    async fn record(&self, record: AuditRecord);
+}
+
+/// Appends every record as a line of JSON to a file.
+#[derive(Debug)]
+pub struct JsonlAuditSink(Mutex<File>);
+
+impl JsonlAuditSink {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self(Mutex::new(file)))
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for JsonlAuditSink {
+    async fn record(&self, record: AuditRecord) {
+        let Ok(mut line) = serde_json::to_vec(&record) else {
+            return;
+        };
+        line.push(b'\n');
+        // A full disk must not fail the tool call itself.
+        let _ = self.0.lock().unwrap().write_all(&line);
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+
+use crate::tools::ToolCallResult;
+
+/// Stores tool results keyed by tool name and normalized arguments, see
+/// [`normalize_arguments`]. For calls with a scoped
+/// [`crate::context::ToolContext`] the key is `[scope, arguments]`.
+#[async_trait::async_trait]
+pub trait ToolCache: fmt::Debug + Send + Sync {
+    async fn get(&self, name: &str, arguments: &str) -> Option<ToolCallResult>;
+    async fn set(&self, name: &str, arguments: &str, result: ToolCallResult);
+}
+
+/// The arguments as compact JSON with sorted keys, so that calls differing
+/// only in formatting or key order share a cache entry.
+pub fn normalize_arguments(arguments: &serde_json::Value) -> String {
+    fn sorted(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), sorted(value)))
+                    .collect()
+            }
+            serde_json::Value::Array(items) => items.iter().map(sorted).collect(),
+            value => value.clone(),
+        }
+    }
+    sorted(arguments).to_string()
+}
+
+/// Keeps results in memory, optionally expiring them after a TTL and
+/// evicting the oldest once over capacity.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<IndexMap<(String, String), (ToolCallResult, Instant)>>,
+    ttl: Option<Duration>,
+    capacity: Option<usize>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolCache for MemoryCache {
+    async fn get(&self, name: &str, arguments: &str) -> Option<ToolCallResult> {
+        let mut entries = self.entries.lock().unwrap();
+        let key = (name.to_string(), arguments.to_string());
+        let (result, inserted) = entries.get(&key)?;
+        if self.ttl.is_some_and(|ttl| inserted.elapsed() > ttl) {
+            entries.shift_remove(&key);
+            return None;
+        }
+        Some(result.clone())
+    }
+    async fn set(&self, name: &str, arguments: &str, result: ToolCallResult) {
+        let mut entries = self.entries.lock().unwrap();
+        let key = (name.to_string(), arguments.to_string());
+        entries.shift_remove(&key);
+        entries.insert(key, (result, Instant::now()));
+        if let Some(capacity) = self.capacity {
+            while entries.len() > capacity {
+                entries.shift_remove_index(0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_normalize_arguments() {
+        assert_eq!(
+            normalize_arguments(&json!({"b": [{"d": 1, "c": 2}], "a": null})),
+            r#"{"a":null,"b":[{"c":2,"d":1}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_limits() {
+        let cache = MemoryCache::new().with_capacity(2);
+        for arguments in ["1", "2", "3"] {
+            cache
+                .set("t", arguments, ToolCallResult::text("id", arguments))
+                .await;
+        }
+        assert!(cache.get("t", "1").await.is_none());
+        assert_eq!(cache.get("t", "3").await.unwrap().to_text(), "3");
+
+        let cache = MemoryCache::new().with_ttl(Duration::from_millis(10));
+        cache.set("t", "1", ToolCallResult::text("id", "1")).await;
+        assert!(cache.get("t", "1").await.is_some());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(cache.get("t", "1").await.is_none());
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+};
+
+/// Values handed to every tool of a [`crate::tools::Tools::call_tools_with_context`]
+/// run, one per type, e.g. the current user or a database pool.
+///
+/// Results of calls with values but no [`ToolContext::with_scope`] are never
+/// cached, as they may depend on the values.
+#[derive(Default)]
+pub struct ToolContext {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    scope: Option<String>,
+}
+
+impl ToolContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Stores `value`, returning the previous value of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous)
+    }
+    pub fn with<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.insert(value);
+        self
+    }
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    /// Who the results belong to, e.g. the user id. Cached results are
+    /// only shared between calls with the same scope.
+    pub fn with_scope(mut self, scope: impl ToString) -> Self {
+        self.scope = Some(scope.to_string());
+        self
+    }
+    pub fn scope(&self) -> Option<&str> {
+        self.scope.as_deref()
+    }
+}
+
+impl fmt::Debug for ToolContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolContext")
+            .field("len", &self.values.len())
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
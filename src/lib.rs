@@ -1,9 +1,471 @@
+pub mod args;
+pub mod audit;
+pub mod cache;
+pub mod context;
+pub mod lint;
+pub mod metrics;
+pub mod middleware;
+pub mod schema;
+pub mod snapshot;
 pub mod tools;
+pub mod validate;
 
-use std::collections::HashMap;
+// Lets the paths emitted by the macros resolve inside this crate too.
+extern crate self as ai_tools_ox;
 
+#[cfg(feature = "derive")]
+pub use ai_tools_ox_derive::{
+    tool, tool_from_fn, toolbox, EnumJsonify, FromToolArgs, Object, ToTool,
+};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use serde;
+    pub use serde_json;
+}
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no JSON schema",
+    label = "`{Self}` does not implement `Jsonify`",
+    note = "implement `Jsonify` for `{Self}` or derive it with `#[derive(Object)]`"
+)]
 pub trait Jsonify {
-    fn jsonify() -> serde_json::Value;
+    /// Whether the value may be omitted, i.e. left out of `required`.
+    const OPTIONAL: bool = false;
+
+    fn jsonify() -> serde_json::Value {
+        root_schema(|| Self::jsonify_with(&JsonifyConfig::default()))
+    }
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value;
+}
+
+/// Enums serialized as plain strings, usually derived with
+/// `#[derive(EnumJsonify)]`.
+pub trait EnumJsonify {
+    /// The variant names as they appear on the wire.
+    const VARIANTS: &'static [&'static str];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaForm {
+    /// Bare type names such as `"number[]"`.
+    Legacy,
+    /// JSON Schema objects such as `{"type": "array", "items": {...}}`.
+    #[default]
+    Structured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimitiveType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+/// How arbitrary-precision decimals are expected on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecimalRepr {
+    /// `"12.50"`, lossless.
+    String,
+    /// `12.5`, subject to `f64` rounding on the model's side.
+    Number,
+    #[default]
+    StringOrNumber,
+}
+
+/// How `Duration` is expected on the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DurationRepr {
+    /// `1.5`, seconds as a number.
+    #[default]
+    Seconds,
+    /// `"PT1.5S"`.
+    Iso8601,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonifyConfig {
+    /// Emit `"integer"` rather than `"number"` for integer types.
+    pub distinguish_integers: bool,
+    /// Bound integer types by their `MIN`/`MAX` with `minimum`/`maximum`.
+    pub integer_bounds: bool,
+    /// Emit `Option<T>` as `["<type>", "null"]` rather than as `T`.
+    pub nullable_type_arrays: bool,
+    pub form: SchemaForm,
+    pub decimal: DecimalRepr,
+    pub duration: DurationRepr,
+}
+
+impl Default for JsonifyConfig {
+    fn default() -> Self {
+        Self {
+            distinguish_integers: true,
+            integer_bounds: false,
+            nullable_type_arrays: false,
+            form: SchemaForm::default(),
+            decimal: DecimalRepr::default(),
+            duration: DurationRepr::default(),
+        }
+    }
+}
+
+impl JsonifyConfig {
+    pub fn primitive(&self, primitive: PrimitiveType) -> serde_json::Value {
+        let name = match primitive {
+            PrimitiveType::String => "string",
+            PrimitiveType::Integer if self.distinguish_integers => "integer",
+            PrimitiveType::Integer | PrimitiveType::Number => "number",
+            PrimitiveType::Boolean => "boolean",
+        };
+        match self.form {
+            SchemaForm::Legacy => json!(name),
+            SchemaForm::Structured => json!({ "type": name }),
+        }
+    }
+    pub fn decimal(&self) -> serde_json::Value {
+        let schema = match self.decimal {
+            DecimalRepr::String => json!("string"),
+            DecimalRepr::Number => json!("number"),
+            DecimalRepr::StringOrNumber => json!(["string", "number"]),
+        };
+        match self.form {
+            SchemaForm::Legacy => schema,
+            SchemaForm::Structured => json!({ "type": schema }),
+        }
+    }
+    /// 128-bit bounds are outside what JSON numbers can carry and are left
+    /// out.
+    pub fn integer(&self, minimum: i128, maximum: u128) -> serde_json::Value {
+        let schema = self.primitive(PrimitiveType::Integer);
+        if !self.integer_bounds {
+            return schema;
+        }
+        let mut keywords = serde_json::Map::new();
+        if let Ok(minimum) = i64::try_from(minimum) {
+            keywords.insert("minimum".to_string(), json!(minimum));
+        }
+        if let Ok(maximum) = u64::try_from(maximum) {
+            keywords.insert("maximum".to_string(), json!(maximum));
+        }
+        self.constrained(schema, serde_json::Value::Object(keywords))
+    }
+    /// A string in a well-known `format` such as `"date-time"`.
+    pub fn formatted(&self, format: &str) -> serde_json::Value {
+        match self.form {
+            SchemaForm::Legacy => json!("string"),
+            SchemaForm::Structured => json!({ "type": "string", "format": format }),
+        }
+    }
+    /// Adds keywords to a schema, legacy schemas have nowhere to carry them
+    /// so they are dropped.
+    pub fn constrained(
+        &self,
+        mut schema: serde_json::Value,
+        keywords: serde_json::Value,
+    ) -> serde_json::Value {
+        if let (SchemaForm::Structured, Some(schema), serde_json::Value::Object(keywords)) =
+            (self.form, schema.as_object_mut(), keywords)
+        {
+            schema.extend(keywords);
+        }
+        schema
+    }
+    /// Legacy type names only exist for primitives, anything else falls back
+    /// to the structured form.
+    pub fn array(&self, items: serde_json::Value) -> serde_json::Value {
+        match (self.form, items.as_str()) {
+            (SchemaForm::Legacy, Some(items)) => json!(format!("{items}[]")),
+            _ => json!({ "type": "array", "items": items }),
+        }
+    }
+    /// An array of distinct items.
+    pub fn set(&self, items: serde_json::Value) -> serde_json::Value {
+        let mut schema = self.array(items);
+        if let Some(schema) = schema.as_object_mut() {
+            schema.insert("uniqueItems".to_string(), json!(true));
+        }
+        schema
+    }
+    /// A fixed-length array with one schema per position.
+    pub fn tuple(&self, items: Vec<serde_json::Value>) -> serde_json::Value {
+        let len = items.len();
+        json!({
+            "type": "array",
+            "prefixItems": items,
+            "minItems": len,
+            "maxItems": len,
+        })
+    }
+    pub fn map(&self, keys: serde_json::Value, values: serde_json::Value) -> serde_json::Value {
+        match (self.form, keys.as_str(), values.as_str()) {
+            (SchemaForm::Legacy, Some(keys), Some(values)) => {
+                json!(format!("Map<{keys}, {values}>"))
+            }
+            _ => json!({ "type": "object", "additionalProperties": values }),
+        }
+    }
+    pub fn nullable(&self, schema: serde_json::Value) -> serde_json::Value {
+        match self.nullable_type_arrays {
+            true => self.or_null(schema),
+            false => schema,
+        }
+    }
+    /// Also accepts `null`, regardless of `nullable_type_arrays`. Used for
+    /// optional properties that strict mode lists as required.
+    pub fn or_null(&self, schema: serde_json::Value) -> serde_json::Value {
+        let accepts_null = |types: &serde_json::Value| {
+            types == "null" || types.as_array().is_some_and(|t| t.contains(&json!("null")))
+        };
+        if schema.get("type").is_some_and(accepts_null) {
+            return schema;
+        }
+        match self.form {
+            SchemaForm::Legacy => json!([schema, "null"]),
+            SchemaForm::Structured => match schema.get("type").cloned() {
+                Some(serde_json::Value::String(name)) => {
+                    let mut schema = schema;
+                    schema["type"] = json!([name, "null"]);
+                    schema
+                }
+                _ => json!({ "anyOf": [schema, { "type": "null" }] }),
+            },
+        }
+    }
+    /// Combines a field's type schema with its keywords (`description`, ...).
+    pub fn property(
+        &self,
+        schema: serde_json::Value,
+        mut keywords: serde_json::Value,
+    ) -> serde_json::Value {
+        canonicalize_numbers(&mut keywords);
+        let mut property = match self.form {
+            SchemaForm::Legacy => json!({ "type": schema }),
+            SchemaForm::Structured => schema,
+        };
+        if let (Some(property), serde_json::Value::Object(keywords)) =
+            (property.as_object_mut(), keywords)
+        {
+            property.extend(keywords);
+        }
+        property
+    }
+    /// Forbids properties not listed in an object schema.
+    pub fn closed(&self, mut schema: serde_json::Value) -> serde_json::Value {
+        if let (SchemaForm::Structured, Some(schema)) = (self.form, schema.as_object_mut()) {
+            schema.insert("additionalProperties".to_string(), json!(false));
+        }
+        schema
+    }
+    /// Generates the schema of a named type. A type that contains itself is
+    /// defined once under `$defs` of the root schema and referenced with
+    /// `$ref`, as are later uses of an already defined type.
+    ///
+    /// `path` identifies the type, e.g. its [`std::any::type_name`]. Its last
+    /// segment names the definition unless another type already took that
+    /// name, in which case the full path is used.
+    pub fn named(
+        &self,
+        path: &str,
+        schema: impl FnOnce() -> serde_json::Value,
+    ) -> serde_json::Value {
+        root_schema(|| {
+            let name = DEFINITIONS.with(|definitions| {
+                let mut definitions = definitions.borrow_mut();
+                let definitions = definitions.as_mut().expect("inside root_schema");
+                definitions.key(path)
+            });
+            let name = name.as_str();
+            let reference = match self.form {
+                SchemaForm::Legacy => json!("object"),
+                SchemaForm::Structured => json!({ "$ref": format!("#/$defs/{name}") }),
+            };
+            let seen = DEFINITIONS.with(|definitions| {
+                let mut definitions = definitions.borrow_mut();
+                let definitions = definitions.as_mut().expect("inside root_schema");
+                if definitions.defs.contains_key(name) {
+                    return true;
+                }
+                if let Some((_, recursive)) =
+                    definitions.in_progress.iter_mut().find(|(n, _)| n == name)
+                {
+                    *recursive = true;
+                    return true;
+                }
+                definitions.in_progress.push((name.to_string(), false));
+                false
+            });
+            if seen {
+                return reference;
+            }
+            let value = schema();
+            DEFINITIONS.with(|definitions| {
+                let mut definitions = definitions.borrow_mut();
+                let definitions = definitions.as_mut().expect("inside root_schema");
+                let (_, recursive) = definitions.in_progress.pop().expect("pushed above");
+                if recursive && self.form == SchemaForm::Structured {
+                    definitions.defs.insert(name.to_string(), value.clone());
+                }
+                // The outermost type stays inline, `$defs` is attached to it.
+                match recursive && !definitions.in_progress.is_empty() {
+                    true => reference,
+                    false => value,
+                }
+            })
+        })
+    }
+    /// Merges the properties and `required` of a flattened field's object
+    /// schema into those of its parent.
+    pub fn flatten(
+        &self,
+        properties: &mut serde_json::Value,
+        required: &mut Vec<String>,
+        schema: serde_json::Value,
+        optional: bool,
+    ) {
+        let (inner, inner_required) = match self.form {
+            SchemaForm::Legacy => (Some(schema), None),
+            SchemaForm::Structured => (
+                schema.get("properties").cloned(),
+                schema.get("required").cloned(),
+            ),
+        };
+        if let (Some(properties), Some(serde_json::Value::Object(inner))) =
+            (properties.as_object_mut(), inner)
+        {
+            properties.extend(inner);
+        }
+        if let (false, Some(serde_json::Value::Array(inner))) = (optional, inner_required) {
+            required.extend(
+                inner
+                    .iter()
+                    .filter_map(|name| name.as_str())
+                    .map(String::from),
+            );
+        }
+    }
+    pub fn object(
+        &self,
+        properties: serde_json::Value,
+        required: Vec<String>,
+    ) -> serde_json::Value {
+        match self.form {
+            SchemaForm::Legacy => properties,
+            SchemaForm::Structured => json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            }),
+        }
+    }
+}
+
+/// Re-encodes every number from its integer or `f64` value so that schemas
+/// serialize the same whether or not `serde_json/arbitrary_precision` is
+/// enabled, which otherwise keeps the source text of parsed numbers (`1e2`,
+/// `1.50`).
+pub fn canonicalize_numbers(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Number(number) => {
+            let canonical = if let Some(n) = number.as_u64() {
+                serde_json::Number::from(n)
+            } else if let Some(n) = number.as_i64() {
+                serde_json::Number::from(n)
+            } else if let Some(n) = number.as_f64().and_then(serde_json::Number::from_f64) {
+                n
+            } else {
+                return;
+            };
+            *number = canonical;
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(canonicalize_numbers),
+        serde_json::Value::Object(map) => map.values_mut().for_each(canonicalize_numbers),
+        _ => {}
+    }
+}
+
+#[derive(Default)]
+struct Definitions {
+    /// Types whose schema is being generated and whether they were
+    /// referenced from within themselves.
+    in_progress: Vec<(String, bool)>,
+    defs: serde_json::Map<String, serde_json::Value>,
+    /// `$defs` key assigned to each type path.
+    keys: HashMap<String, String>,
+}
+
+impl Definitions {
+    fn key(&mut self, path: &str) -> String {
+        if let Some(key) = self.keys.get(path) {
+            return key.clone();
+        }
+        let short = path.rsplit("::").next().unwrap_or(path);
+        let key = match self.keys.values().any(|key| key == short) {
+            true => path.replace("::", "."),
+            false => short.to_string(),
+        };
+        self.keys.insert(path.to_string(), key.clone());
+        key
+    }
+}
+
+thread_local! {
+    static DEFINITIONS: RefCell<Option<Definitions>> = const { RefCell::new(None) };
+}
+
+/// Generates a schema document, attaching the `$defs` collected by
+/// [`JsonifyConfig::named`]. Nested calls are part of the outer document.
+pub fn root_schema(schema: impl FnOnce() -> serde_json::Value) -> serde_json::Value {
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            DEFINITIONS.with(|definitions| definitions.borrow_mut().take());
+        }
+    }
+
+    if DEFINITIONS.with(|definitions| definitions.borrow().is_some()) {
+        return schema();
+    }
+    DEFINITIONS.with(|definitions| *definitions.borrow_mut() = Some(Definitions::default()));
+    let reset = Reset;
+    let mut value = schema();
+    let defs = DEFINITIONS
+        .with(|definitions| definitions.borrow_mut().take())
+        .map(|definitions| definitions.defs)
+        .unwrap_or_default();
+    drop(reset);
+    if let (false, Some(object)) = (defs.is_empty(), value.as_object_mut()) {
+        object.insert("$defs".to_string(), serde_json::Value::Object(defs));
+    }
+    value
+}
+
+/// Writes the pretty-printed schema of `T` to `path`, e.g. from an xtask or
+/// from the build script of a crate that has `T` as a build dependency, so
+/// other services can consume it.
+pub fn export_schema<T: Jsonify>(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let schema = serde_json::to_string_pretty(&T::jsonify())?;
+    std::fs::write(path, schema + "\n")
 }
 
 macro_rules! impl_jsonify {
@@ -11,8 +473,8 @@ macro_rules! impl_jsonify {
         $(
             $(
                 impl Jsonify for $t {
-                    fn jsonify() -> serde_json::Value {
-                        serde_json::Value::String($result.to_string())
+                    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                        config.primitive($result)
                     }
                 }
             )*
@@ -21,50 +483,300 @@ macro_rules! impl_jsonify {
 }
 
 impl_jsonify!(
-    &str | String | char => "string",
-    i8 | i16 | i32 | i64 | i128 => "number",
-    u8 | u16 | u32 | u64 | u128 => "number",
-    f32 | f64 => "number",
-    bool => "boolean",
+    str | String | char | Path | PathBuf => PrimitiveType::String,
+    f32 | f64 => PrimitiveType::Number,
+    bool => PrimitiveType::Boolean,
+);
+
+macro_rules! impl_jsonify_integer {
+    ( $( $t:ty ),* $(,)? ) => {
+        $(
+            impl Jsonify for $t {
+                fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                    config.integer(<$t>::MIN as i128, <$t>::MAX as u128)
+                }
+            }
+        )*
+    };
+}
+
+impl_jsonify_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_jsonify_nonzero {
+    ( $( $( $t:ty )|+ => $keywords:tt ),* $(,)? ) => {
+        $(
+            $(
+                impl Jsonify for $t {
+                    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                        config.constrained(config.primitive(PrimitiveType::Integer), json!($keywords))
+                    }
+                }
+            )*
+        )*
+    };
+}
+
+impl_jsonify_nonzero!(
+    NonZeroU8 | NonZeroU16 | NonZeroU32 | NonZeroU64 | NonZeroU128 | NonZeroUsize
+        => { "exclusiveMinimum": 0 },
+    NonZeroI8 | NonZeroI16 | NonZeroI32 | NonZeroI64 | NonZeroI128 | NonZeroIsize
+        => { "not": { "const": 0 } },
 );
 
+impl<T: Jsonify> Jsonify for Option<T> {
+    const OPTIONAL: bool = true;
+
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.nullable(<T>::jsonify_with(config))
+    }
+}
+
+macro_rules! impl_jsonify_transparent {
+    ( $( $t:ty ),* $(,)? ) => {
+        $(
+            impl<T: Jsonify + ?Sized> Jsonify for $t {
+                const OPTIONAL: bool = T::OPTIONAL;
+
+                fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                    <T>::jsonify_with(config)
+                }
+            }
+        )*
+    };
+}
+
+impl_jsonify_transparent!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+impl<T: Jsonify + ToOwned + ?Sized> Jsonify for Cow<'_, T> {
+    const OPTIONAL: bool = T::OPTIONAL;
+
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        <T>::jsonify_with(config)
+    }
+}
+
+impl<T: Jsonify> Jsonify for [T] {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.array(<T>::jsonify_with(config))
+    }
+}
+
 impl<T: Jsonify> Jsonify for Vec<T> {
-    fn jsonify() -> serde_json::Value {
-        serde_json::Value::String(format!("{}[]", <T>::jsonify().as_str().unwrap()))
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.array(<T>::jsonify_with(config))
+    }
+}
+
+impl<T: Jsonify> Jsonify for VecDeque<T> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.array(<T>::jsonify_with(config))
+    }
+}
+
+impl<T: Jsonify> Jsonify for HashSet<T> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.set(<T>::jsonify_with(config))
+    }
+}
+
+impl<T: Jsonify> Jsonify for BTreeSet<T> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.set(<T>::jsonify_with(config))
     }
 }
 
 impl<K: Jsonify, V: Jsonify> Jsonify for HashMap<K, V> {
-    fn jsonify() -> serde_json::Value {
-        serde_json::Value::String(format!(
-            "Map<{}, {}>",
-            <K>::jsonify().as_str().unwrap(),
-            <V>::jsonify().as_str().unwrap()
-        ))
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.map(<K>::jsonify_with(config), <V>::jsonify_with(config))
+    }
+}
+
+impl<K: Jsonify, V: Jsonify> Jsonify for BTreeMap<K, V> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.map(<K>::jsonify_with(config), <V>::jsonify_with(config))
+    }
+}
+
+macro_rules! impl_jsonify_formatted {
+    ( $( $( $t:ty )|+ => $format:expr ),* $(,)? ) => {
+        $(
+            $(
+                impl Jsonify for $t {
+                    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                        config.formatted($format)
+                    }
+                }
+            )*
+        )*
+    };
+}
+
+impl_jsonify_formatted!(
+    Ipv4Addr => "ipv4",
+    Ipv6Addr => "ipv6",
+);
+
+/// Either address format, legacy names cannot express the choice.
+impl Jsonify for IpAddr {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        match config.form {
+            SchemaForm::Legacy => json!("string"),
+            SchemaForm::Structured => json!({
+                "anyOf": [Ipv4Addr::jsonify_with(config), Ipv6Addr::jsonify_with(config)]
+            }),
+        }
+    }
+}
+
+// There is no standard format for `host:port`, the nonstandard hint still
+// tells the model what to send.
+impl_jsonify_formatted!(
+    SocketAddr | SocketAddrV4 | SocketAddrV6 => "socket-address",
+);
+
+impl Jsonify for Duration {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        match config.duration {
+            DurationRepr::Seconds => config.constrained(
+                config.primitive(PrimitiveType::Number),
+                json!({ "minimum": 0 }),
+            ),
+            DurationRepr::Iso8601 => config.formatted("duration"),
+        }
+    }
+}
+
+impl Jsonify for serde_json::Value {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        match config.form {
+            SchemaForm::Legacy => json!("any"),
+            SchemaForm::Structured => json!({}),
+        }
+    }
+}
+
+impl Jsonify for serde_json::Map<String, serde_json::Value> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        match config.form {
+            SchemaForm::Legacy => json!("object"),
+            SchemaForm::Structured => json!({ "type": "object", "additionalProperties": true }),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Jsonify for chrono::DateTime<Tz> {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("date-time")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Jsonify for chrono::NaiveDate {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("date")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Jsonify for chrono::NaiveTime {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("time")
+    }
+}
+
+#[cfg(feature = "time")]
+impl Jsonify for time::OffsetDateTime {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("date-time")
+    }
+}
+
+#[cfg(feature = "time")]
+impl Jsonify for time::Date {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("date")
+    }
+}
+
+#[cfg(feature = "time")]
+impl Jsonify for time::Time {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("time")
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl Jsonify for rust_decimal::Decimal {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.decimal()
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl Jsonify for bigdecimal::BigDecimal {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.decimal()
+    }
+}
+
+#[cfg(feature = "url")]
+impl Jsonify for url::Url {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("uri")
     }
 }
 
+#[cfg(feature = "uuid")]
+impl Jsonify for uuid::Uuid {
+    fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+        config.formatted("uuid")
+    }
+}
+
+macro_rules! impl_jsonify_tuple {
+    ( $( ( $( $t:ident ),+ ) ),* $(,)? ) => {
+        $(
+            impl<$( $t: Jsonify ),+> Jsonify for ( $( $t, )+ ) {
+                fn jsonify_with(config: &JsonifyConfig) -> serde_json::Value {
+                    config.tuple(vec![ $( <$t>::jsonify_with(config) ),+ ])
+                }
+            }
+        )*
+    };
+}
+
+impl_jsonify_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+    (A, B, C, D, E, F, G, H, I),
+    (A, B, C, D, E, F, G, H, I, J),
+    (A, B, C, D, E, F, G, H, I, J, K),
+    (A, B, C, D, E, F, G, H, I, J, K, L),
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ai_tools_ox_derive::Object as JsonifyObject;
+
     #[test]
     fn test_jsonify() {
-        assert_eq!(
-            String::jsonify(),
-            serde_json::Value::String("string".to_string())
-        );
-        assert_eq!(
-            i32::jsonify(),
-            serde_json::Value::String("number".to_string())
-        );
+        assert_eq!(String::jsonify(), json!({"type": "string"}));
+        assert_eq!(i32::jsonify(), json!({"type": "integer"}));
         assert_eq!(
             Vec::<i32>::jsonify(),
-            serde_json::Value::String("number[]".to_string())
+            json!({"type": "array", "items": {"type": "integer"}})
         );
         assert_eq!(
             HashMap::<String, String>::jsonify(),
-            serde_json::Value::String("Map<string, string>".to_string())
+            json!({"type": "object", "additionalProperties": {"type": "string"}})
         );
 
         #[allow(dead_code)]
@@ -77,4 +789,1227 @@ mod tests {
         }
         println!("{}", serde_json::to_string_pretty(&Foo::jsonify()).unwrap())
     }
+
+    #[test]
+    fn test_rename_all() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(rename_all = "camelCase")]
+        struct Foo {
+            user_id: i32,
+            display_name: String,
+        }
+        let json = &Foo::jsonify()["properties"];
+        assert!(json.get("userId").is_some());
+        assert!(json.get("displayName").is_some());
+        assert!(json.get("user_id").is_none());
+    }
+
+    #[test]
+    fn test_deprecated_field() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Foo {
+            query: String,
+            #[description(description = "use query instead", deprecated)]
+            q: String,
+        }
+        let json = &Foo::jsonify()["properties"];
+        assert_eq!(json["q"]["deprecated"], serde_json::Value::Bool(true));
+        assert!(json["query"].get("deprecated").is_none());
+    }
+
+    #[test]
+    fn test_sensitive_field() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Foo {
+            user: String,
+            #[description(sensitive)]
+            password: String,
+        }
+        let json = &Foo::jsonify()["properties"];
+        assert_eq!(
+            json["password"]["x-sensitive"],
+            serde_json::Value::Bool(true)
+        );
+        assert!(json["user"].get("x-sensitive").is_none());
+    }
+
+    #[test]
+    fn test_jsonify_config() {
+        let legacy = JsonifyConfig {
+            distinguish_integers: false,
+            form: SchemaForm::Legacy,
+            ..Default::default()
+        };
+        let structured = JsonifyConfig {
+            distinguish_integers: true,
+            nullable_type_arrays: true,
+            form: SchemaForm::Structured,
+            ..Default::default()
+        };
+        assert_eq!(u32::jsonify_with(&legacy), json!("number"));
+        assert_eq!(u32::jsonify_with(&structured), json!({"type": "integer"}));
+        assert_eq!(Option::<String>::jsonify_with(&legacy), json!("string"));
+        assert_eq!(
+            Option::<String>::jsonify_with(&structured),
+            json!({"type": ["string", "null"]})
+        );
+        assert_eq!(
+            Vec::<i32>::jsonify_with(&structured),
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Foo {
+            #[description(description = "a is some number")]
+            a: i32,
+        }
+        assert_eq!(
+            Foo::jsonify_with(&legacy),
+            json!({"a": {"type": "number", "description": "a is some number"}})
+        );
+        assert_eq!(
+            Foo::jsonify_with(&structured),
+            json!({
+                "type": "object",
+                "properties": {"a": {"type": "integer", "description": "a is some number"}},
+                "required": ["a"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_tagged_enum() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(tag = "type")]
+        enum Action {
+            Move {
+                #[description(description = "distance")]
+                x: i32,
+            },
+            Say {
+                text: String,
+            },
+            Stop,
+        }
+        assert_eq!(
+            Action::jsonify(),
+            json!({
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": {"type": "string", "const": "Move"},
+                            "x": {"type": "integer", "description": "distance"},
+                        },
+                        "required": ["type", "x"],
+                    },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "type": {"type": "string", "const": "Say"},
+                            "text": {"type": "string"},
+                        },
+                        "required": ["type", "text"],
+                    },
+                    {
+                        "type": "object",
+                        "properties": {"type": {"type": "string", "const": "Stop"}},
+                        "required": ["type"],
+                    },
+                ]
+            })
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(tag = "kind", rename_all = "snake_case")]
+        enum Event {
+            KeyPress { key: char },
+        }
+        let schema = Event::jsonify();
+        assert_eq!(
+            schema["oneOf"][0]["properties"]["kind"],
+            json!({"type": "string", "const": "key_press"})
+        );
+        assert_eq!(schema["oneOf"][0]["required"], json!(["kind", "key"]));
+    }
+
+    // Run with and without `--features arbitrary_precision`, the expected
+    // output is the same for both.
+    #[test]
+    fn test_canonical_numbers() {
+        let keywords =
+            serde_json::from_str(r#"{"minimum": -0.50, "maximum": 1e2, "default": 10}"#).unwrap();
+        let property = JsonifyConfig::default().property(json!({"type": "number"}), keywords);
+        assert_eq!(
+            serde_json::to_string(&property).unwrap(),
+            r#"{"type":"number","minimum":-0.5,"maximum":100.0,"default":10}"#
+        );
+    }
+
+    #[test]
+    fn test_property_order() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Foo {
+            d: String,
+            #[description(order = 2)]
+            a: String,
+            c: String,
+            #[description(order = 1)]
+            b: String,
+        }
+        let json = Foo::jsonify();
+        let keys = json["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["b", "a", "d", "c"]);
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Foo {
+            a: String,
+            b: Option<String>,
+        }
+        let json = Foo::jsonify();
+        assert_eq!(json["properties"]["b"], json!({"type": "string"}));
+        assert_eq!(json["required"], json!(["a"]));
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(tag = "type")]
+        enum Action {
+            Say { text: String, volume: Option<i32> },
+        }
+        assert_eq!(
+            Action::jsonify()["oneOf"][0]["required"],
+            json!(["type", "text"])
+        );
+    }
+
+    #[test]
+    fn test_vec_of_objects() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+        let items = json!({
+            "type": "object",
+            "properties": {"x": {"type": "number"}, "y": {"type": "number"}},
+            "required": ["x", "y"],
+        });
+        assert_eq!(
+            Vec::<Point>::jsonify(),
+            json!({"type": "array", "items": items})
+        );
+
+        let legacy = JsonifyConfig {
+            form: SchemaForm::Legacy,
+            ..Default::default()
+        };
+        assert_eq!(
+            Vec::<Point>::jsonify_with(&legacy),
+            json!({"type": "array", "items": Point::jsonify_with(&legacy)})
+        );
+        assert_eq!(
+            Vec::<Vec<Point>>::jsonify(),
+            json!({"type": "array", "items": {"type": "array", "items": items}})
+        );
+    }
+
+    #[test]
+    fn test_unit_enum() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(rename_all = "lowercase")]
+        enum Unit {
+            Celsius,
+            Fahrenheit,
+            #[object(rename = "K")]
+            Kelvin,
+        }
+        assert_eq!(
+            Unit::jsonify(),
+            json!({"type": "string", "enum": ["celsius", "fahrenheit", "K"]})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        enum Direction {
+            Up,
+            Down,
+        }
+        assert_eq!(
+            Direction::jsonify(),
+            json!({"type": "string", "enum": ["Up", "Down"]})
+        );
+    }
+
+    #[test]
+    fn test_newtype_variant() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct DeleteArgs {
+            id: u64,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(tag = "action", rename_all = "snake_case")]
+        enum Action {
+            Create { name: String },
+            Delete(DeleteArgs),
+        }
+        let schema = Action::jsonify();
+        assert_eq!(
+            schema["oneOf"][0]["properties"]["action"]["const"],
+            "create"
+        );
+        assert_eq!(
+            schema["oneOf"][1],
+            json!({
+                "allOf": [
+                    {
+                        "type": "object",
+                        "properties": {"action": {"type": "string", "const": "delete"}},
+                        "required": ["action"],
+                    },
+                    DeleteArgs::jsonify(),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        assert_eq!(
+            <(f64, f64)>::jsonify(),
+            json!({
+                "type": "array",
+                "prefixItems": [{"type": "number"}, {"type": "number"}],
+                "minItems": 2,
+                "maxItems": 2,
+            })
+        );
+        assert_eq!(
+            <(String, bool, i32)>::jsonify()["prefixItems"],
+            json!([{"type": "string"}, {"type": "boolean"}, {"type": "integer"}])
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        assert_eq!(
+            VecDeque::<u8>::jsonify(),
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+        let set = json!({"type": "array", "items": {"type": "string"}, "uniqueItems": true});
+        assert_eq!(HashSet::<String>::jsonify(), set);
+        assert_eq!(BTreeSet::<String>::jsonify(), set);
+        assert_eq!(
+            BTreeMap::<String, bool>::jsonify(),
+            json!({"type": "object", "additionalProperties": {"type": "boolean"}})
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::jsonify(),
+            json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(
+            chrono::NaiveDate::jsonify(),
+            json!({"type": "string", "format": "date"})
+        );
+        assert_eq!(
+            chrono::NaiveTime::jsonify_with(&JsonifyConfig {
+                form: SchemaForm::Legacy,
+                ..Default::default()
+            }),
+            json!("string")
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        assert_eq!(
+            time::OffsetDateTime::jsonify(),
+            json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(
+            time::Time::jsonify(),
+            json!({"type": "string", "format": "time"})
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Order {
+            id: uuid::Uuid,
+            parent: Option<uuid::Uuid>,
+        }
+
+        assert_eq!(
+            Order::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string", "format": "uuid"},
+                    "parent": {"type": "string", "format": "uuid"},
+                },
+                "required": ["id"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_decimal_repr() {
+        let mut config = JsonifyConfig::default();
+        assert_eq!(config.decimal(), json!({"type": ["string", "number"]}));
+        config.decimal = DecimalRepr::String;
+        assert_eq!(config.decimal(), json!({"type": "string"}));
+        config.form = SchemaForm::Legacy;
+        config.decimal = DecimalRepr::Number;
+        assert_eq!(config.decimal(), json!("number"));
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal() {
+        assert_eq!(
+            rust_decimal::Decimal::jsonify(),
+            json!({"type": ["string", "number"]})
+        );
+    }
+
+    #[test]
+    fn test_free_form_json() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Request {
+            body: serde_json::Value,
+            headers: serde_json::Map<String, serde_json::Value>,
+        }
+        assert_eq!(
+            Request::jsonify()["properties"],
+            json!({
+                "body": {},
+                "headers": {"type": "object", "additionalProperties": true},
+            })
+        );
+    }
+
+    #[test]
+    fn test_transparent_wrappers() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Node<'a> {
+            name: Cow<'a, str>,
+            label: &'a str,
+            tags: Box<[String]>,
+            parent: Box<Option<u32>>,
+            weight: Arc<f64>,
+            owner: Rc<String>,
+        }
+        assert_eq!(
+            Node::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string"},
+                    "label": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "parent": {"type": "integer"},
+                    "weight": {"type": "number"},
+                    "owner": {"type": "string"},
+                },
+                "required": ["name", "label", "tags", "weight", "owner"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_nonzero() {
+        assert_eq!(usize::jsonify(), json!({"type": "integer"}));
+        assert_eq!(
+            NonZeroU32::jsonify(),
+            json!({"type": "integer", "exclusiveMinimum": 0})
+        );
+        assert_eq!(
+            NonZeroI64::jsonify(),
+            json!({"type": "integer", "not": {"const": 0}})
+        );
+        let legacy = JsonifyConfig {
+            form: SchemaForm::Legacy,
+            ..Default::default()
+        };
+        assert_eq!(NonZeroUsize::jsonify_with(&legacy), json!("integer"));
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(PathBuf::jsonify(), json!({"type": "string"}));
+        assert_eq!(<&Path>::jsonify(), json!({"type": "string"}));
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            url::Url::jsonify(),
+            json!({"type": "string", "format": "uri"})
+        );
+    }
+
+    #[test]
+    fn test_integer_bounds() {
+        let config = JsonifyConfig {
+            integer_bounds: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            u8::jsonify_with(&config),
+            json!({"type": "integer", "minimum": 0, "maximum": 255})
+        );
+        assert_eq!(
+            i16::jsonify_with(&config),
+            json!({"type": "integer", "minimum": -32768, "maximum": 32767})
+        );
+        assert_eq!(
+            u128::jsonify_with(&config),
+            json!({"type": "integer", "minimum": 0})
+        );
+        assert_eq!(i128::jsonify_with(&config), json!({"type": "integer"}));
+        assert_eq!(f32::jsonify_with(&config), json!({"type": "number"}));
+    }
+
+    #[test]
+    fn test_numeric_range() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Volume {
+            #[description(minimum = 0, maximum = 100)]
+            level: u8,
+            #[description(exclusive_minimum = -1.5, exclusive_maximum = 1.5)]
+            balance: f32,
+        }
+        assert_eq!(
+            Volume::jsonify()["properties"],
+            json!({
+                "level": {"type": "integer", "minimum": 0, "maximum": 100},
+                "balance": {"type": "number", "exclusiveMinimum": -1.5, "exclusiveMaximum": 1.5},
+            })
+        );
+    }
+
+    #[test]
+    fn test_string_constraints() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Signup {
+            #[description(pattern = "^[a-z]+$", min_length = 3, max_length = 64)]
+            username: String,
+            #[description(format = "email")]
+            email: String,
+        }
+        assert_eq!(
+            Signup::jsonify()["properties"],
+            json!({
+                "username": {"type": "string", "pattern": "^[a-z]+$", "minLength": 3, "maxLength": 64},
+                "email": {"type": "string", "format": "email"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_array_constraints() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Post {
+            #[description(min_items = 1, max_items = 5, unique_items)]
+            tags: Vec<String>,
+        }
+        assert_eq!(
+            Post::jsonify()["properties"]["tags"],
+            json!({
+                "type": "array",
+                "items": {"type": "string"},
+                "minItems": 1,
+                "maxItems": 5,
+                "uniqueItems": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_default_value() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Search {
+            query: String,
+            #[description(default = 10)]
+            limit: u32,
+            #[description(default = "en")]
+            language: String,
+        }
+        let schema = Search::jsonify();
+        assert_eq!(schema["properties"]["limit"]["default"], json!(10));
+        assert_eq!(schema["properties"]["language"]["default"], json!("en"));
+        assert_eq!(schema["required"], json!(["query"]));
+    }
+
+    #[test]
+    fn test_examples() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Weather {
+            #[description(examples = ["Warsaw", "Lisbon"])]
+            city: String,
+            #[description(examples = [-5, 30])]
+            celsius: i32,
+        }
+        let properties = &Weather::jsonify()["properties"];
+        assert_eq!(properties["city"]["examples"], json!(["Warsaw", "Lisbon"]));
+        assert_eq!(properties["celsius"]["examples"], json!([-5, 30]));
+    }
+
+    #[test]
+    fn test_strict_object() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(strict)]
+        struct Search {
+            query: String,
+            limit: Option<u32>,
+        }
+        assert_eq!(
+            Search::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string"},
+                    "limit": {"type": ["integer", "null"]},
+                },
+                "required": ["query", "limit"],
+                "additionalProperties": false,
+            })
+        );
+        let config = JsonifyConfig {
+            nullable_type_arrays: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Search::jsonify_with(&config)["properties"]["limit"],
+            json!({"type": ["integer", "null"]})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(strict, tag = "type")]
+        enum Command {
+            Stop { force: Option<bool> },
+        }
+        let variant = &Command::jsonify()["oneOf"][0];
+        assert_eq!(variant["required"], json!(["type", "force"]));
+        assert_eq!(
+            variant["properties"]["force"]["type"],
+            json!(["boolean", "null"])
+        );
+        assert_eq!(variant["additionalProperties"], json!(false));
+    }
+
+    #[test]
+    fn test_recursive_type() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Node {
+            name: String,
+            children: Vec<Node>,
+        }
+        let node = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "children": {"type": "array", "items": {"$ref": "#/$defs/Node"}},
+            },
+            "required": ["name", "children"],
+        });
+        let mut expected = node.clone();
+        expected["$defs"] = json!({ "Node": node });
+        assert_eq!(Node::jsonify(), expected);
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Tree {
+            root: Node,
+            orphans: Vec<Node>,
+        }
+        let schema = Tree::jsonify();
+        assert_eq!(
+            schema["properties"]["root"],
+            json!({"$ref": "#/$defs/Node"})
+        );
+        assert_eq!(
+            schema["properties"]["orphans"]["items"],
+            json!({"$ref": "#/$defs/Node"})
+        );
+        assert!(schema["$defs"].get("Node").is_some());
+        assert!(schema["$defs"].get("Tree").is_none());
+    }
+
+    #[test]
+    fn test_recursive_types_with_same_name() {
+        mod a {
+            use super::*;
+
+            #[allow(dead_code)]
+            #[derive(JsonifyObject)]
+            pub struct Node {
+                pub children: Vec<Node>,
+            }
+        }
+        mod b {
+            use super::*;
+
+            #[allow(dead_code)]
+            #[derive(JsonifyObject)]
+            pub struct Node {
+                pub label: String,
+                pub children: Vec<Node>,
+            }
+        }
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Graph {
+            left: a::Node,
+            right: b::Node,
+        }
+        let schema = Graph::jsonify();
+        let left = schema["properties"]["left"]["$ref"].as_str().unwrap();
+        let right = schema["properties"]["right"]["$ref"].as_str().unwrap();
+        assert_eq!(left, "#/$defs/Node");
+        assert_ne!(left, right);
+        let right = right.trim_start_matches("#/$defs/");
+        assert!(schema["$defs"][right]["properties"].get("label").is_some());
+        assert!(schema["$defs"]["Node"]["properties"].get("label").is_none());
+    }
+
+    #[test]
+    fn test_const_field() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Event {
+            #[description(constant = "v2")]
+            version: String,
+        }
+        assert_eq!(
+            Event::jsonify()["properties"]["version"],
+            json!({"type": "string", "const": "v2"})
+        );
+    }
+
+    #[test]
+    fn test_serde_attributes() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        struct Paging {
+            page: u32,
+            per_page: Option<u32>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Search {
+            #[serde(rename = "q")]
+            query: String,
+            max_results: u32,
+            #[serde(default)]
+            include_archived: bool,
+            #[serde(skip)]
+            callback: Option<fn()>,
+            #[serde(flatten)]
+            paging: Paging,
+        }
+        assert_eq!(
+            Search::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "q": {"type": "string"},
+                    "maxResults": {"type": "integer"},
+                    "includeArchived": {"type": "boolean"},
+                    "page": {"type": "integer"},
+                    "per_page": {"type": "integer"},
+                },
+                "required": ["q", "maxResults", "page"],
+            })
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        #[serde(tag = "kind", rename_all = "snake_case")]
+        enum Shape {
+            Circle {
+                radius: f64,
+            },
+            #[serde(rename = "rect")]
+            Rectangle {
+                width: f64,
+            },
+            #[serde(skip)]
+            Unknown,
+        }
+        let variants = Shape::jsonify()["oneOf"].as_array().unwrap().clone();
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0]["properties"]["kind"]["const"], "circle");
+        assert_eq!(variants[1]["properties"]["kind"]["const"], "rect");
+    }
+
+    #[test]
+    fn test_serde_rename_all_matches_serde() {
+        #[derive(JsonifyObject, serde::Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Kind {
+            HTTPServer,
+            A1B,
+        }
+        let names = [Kind::HTTPServer, Kind::A1B].map(|kind| serde_json::to_value(kind).unwrap());
+        assert_eq!(Kind::jsonify()["enum"], json!(names));
+
+        #[derive(JsonifyObject, serde::Serialize)]
+        #[serde(rename_all = "SCREAMING-KEBAB-CASE")]
+        struct Server {
+            http_server: String,
+            a1_b: u8,
+        }
+        #[derive(JsonifyObject, serde::Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Client {
+            http_client: String,
+            a1_b: u8,
+        }
+        let keys = |value: serde_json::Value| {
+            value
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let server = Server {
+            http_server: String::new(),
+            a1_b: 0,
+        };
+        assert_eq!(
+            keys(Server::jsonify()["properties"].clone()),
+            keys(serde_json::to_value(server).unwrap())
+        );
+        let client = Client {
+            http_client: String::new(),
+            a1_b: 0,
+        };
+        assert_eq!(
+            keys(Client::jsonify()["properties"].clone()),
+            keys(serde_json::to_value(client).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        /// A place on the map.
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Location {
+            /// Latitude in degrees.
+            ///
+            /// North is positive.
+            lat: f64,
+            /// Ignored, the attribute wins.
+            #[description(description = "Longitude in degrees.")]
+            lon: f64,
+        }
+        let schema = Location::jsonify();
+        assert_eq!(schema["description"], "A place on the map.");
+        assert_eq!(
+            schema["properties"]["lat"]["description"],
+            "Latitude in degrees.\n\nNorth is positive."
+        );
+        assert_eq!(
+            schema["properties"]["lon"]["description"],
+            "Longitude in degrees."
+        );
+    }
+
+    #[test]
+    fn test_title_field() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Search {
+            #[description(title = "Query", description = "text to search for")]
+            query: String,
+            #[description(deprecated)]
+            q: String,
+        }
+        let properties = &Search::jsonify()["properties"];
+        assert_eq!(
+            properties["query"],
+            json!({"type": "string", "title": "Query", "description": "text to search for"})
+        );
+        assert_eq!(
+            properties["q"],
+            json!({"type": "string", "deprecated": true})
+        );
+    }
+
+    #[test]
+    fn test_generic_struct() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Page<T> {
+            items: Vec<T>,
+            next: Option<String>,
+        }
+        assert_eq!(
+            Page::<u32>::jsonify()["properties"]["items"],
+            json!({"type": "array", "items": {"type": "integer"}})
+        );
+
+        trait Backend {
+            type Id: Jsonify;
+        }
+        struct Postgres;
+        impl Backend for Postgres {
+            type Id = i64;
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(bound = "B::Id: Jsonify")]
+        struct Lookup<B: Backend> {
+            id: B::Id,
+        }
+        assert_eq!(
+            Lookup::<Postgres>::jsonify()["properties"]["id"],
+            json!({"type": "integer"})
+        );
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(Duration::jsonify(), json!({"type": "number", "minimum": 0}));
+        let config = JsonifyConfig {
+            duration: DurationRepr::Iso8601,
+            ..Default::default()
+        };
+        assert_eq!(
+            Duration::jsonify_with(&config),
+            json!({"type": "string", "format": "duration"})
+        );
+    }
+
+    #[test]
+    fn test_network_addresses() {
+        assert_eq!(
+            Ipv4Addr::jsonify(),
+            json!({"type": "string", "format": "ipv4"})
+        );
+        assert_eq!(
+            IpAddr::jsonify(),
+            json!({"anyOf": [
+                {"type": "string", "format": "ipv4"},
+                {"type": "string", "format": "ipv6"},
+            ]})
+        );
+        assert_eq!(
+            SocketAddr::jsonify(),
+            json!({"type": "string", "format": "socket-address"})
+        );
+    }
+
+    #[test]
+    fn test_newtype_and_unit_structs() {
+        /// Account identifier.
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct UserId(#[description(minimum = 1)] u64);
+        assert_eq!(
+            UserId::jsonify(),
+            json!({"type": "integer", "minimum": 1, "description": "Account identifier."})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        #[object(transparent = false)]
+        struct Wrapped(String);
+        assert_eq!(
+            Wrapped::jsonify(),
+            json!({
+                "type": "array",
+                "prefixItems": [{"type": "string"}],
+                "minItems": 1,
+                "maxItems": 1,
+            })
+        );
+
+        #[derive(JsonifyObject)]
+        struct Ping;
+        assert_eq!(
+            Ping::jsonify(),
+            json!({"type": "object", "properties": {}, "required": []})
+        );
+
+        #[derive(JsonifyObject)]
+        #[object(null)]
+        struct Nothing;
+        assert_eq!(Nothing::jsonify(), json!({"type": "null"}));
+    }
+
+    #[test]
+    fn test_schema_with() {
+        fn timestamp() -> serde_json::Value {
+            json!({"type": "string", "format": "date-time"})
+        }
+
+        struct Opaque;
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Event {
+            #[description(schema_with = "timestamp", description = "when it happened")]
+            at: Opaque,
+        }
+        assert_eq!(
+            Event::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "at": {"type": "string", "format": "date-time", "description": "when it happened"},
+                },
+                "required": ["at"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_enum_representations() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        enum Command {
+            Stop,
+            Move { x: i32 },
+            Say(String),
+        }
+        assert_eq!(
+            Command::jsonify(),
+            json!({"oneOf": [
+                {"type": "string", "const": "Stop"},
+                {
+                    "type": "object",
+                    "properties": {"Move": {
+                        "type": "object",
+                        "properties": {"x": {"type": "integer"}},
+                        "required": ["x"],
+                    }},
+                    "required": ["Move"],
+                    "additionalProperties": false,
+                },
+                {
+                    "type": "object",
+                    "properties": {"Say": {"type": "string"}},
+                    "required": ["Say"],
+                    "additionalProperties": false,
+                },
+            ]})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        #[serde(tag = "t", content = "c")]
+        enum Message {
+            Ping,
+            Text(String),
+        }
+        assert_eq!(
+            Message::jsonify(),
+            json!({"oneOf": [
+                {
+                    "type": "object",
+                    "properties": {"t": {"type": "string", "const": "Ping"}},
+                    "required": ["t"],
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "t": {"type": "string", "const": "Text"},
+                        "c": {"type": "string"},
+                    },
+                    "required": ["t", "c"],
+                },
+            ]})
+        );
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        #[serde(untagged)]
+        enum Target {
+            Id(u64),
+            Point(f64, f64),
+            Named { name: String },
+        }
+        assert_eq!(
+            Target::jsonify(),
+            json!({"anyOf": [
+                {"type": "integer"},
+                {
+                    "type": "array",
+                    "prefixItems": [{"type": "number"}, {"type": "number"}],
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+                {
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}},
+                    "required": ["name"],
+                },
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Location {
+            city: String,
+            zip: Option<String>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Forecast {
+            days: u8,
+            #[description(flatten)]
+            location: Location,
+            #[description(flatten)]
+            fallback: Option<Location>,
+        }
+        assert_eq!(
+            Forecast::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "days": {"type": "integer"},
+                    "city": {"type": "string"},
+                    "zip": {"type": "string"},
+                },
+                "required": ["days", "city"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_rename() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        #[object(rename_all = "camelCase")]
+        #[serde(rename_all = "snake_case")]
+        struct Booking {
+            check_in: String,
+            #[object(rename = "nights")]
+            #[serde(rename = "length")]
+            stay_length: u8,
+        }
+        assert_eq!(Booking::jsonify()["required"], json!(["checkIn", "nights"]));
+    }
+
+    #[test]
+    fn test_skip_field() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Note {
+            text: String,
+            #[description(skip)]
+            id: u64,
+            #[description(skip)]
+            connection: *const u8,
+        }
+        assert_eq!(
+            Note::jsonify(),
+            json!({
+                "type": "object",
+                "properties": {"text": {"type": "string"}},
+                "required": ["text"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_required_override() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject, serde::Deserialize)]
+        struct Query {
+            #[serde(default)]
+            #[description(optional)]
+            limit: u32,
+            #[description(required = false)]
+            offset: u32,
+            #[description(required = true)]
+            cursor: Option<String>,
+            text: String,
+        }
+        assert_eq!(Query::jsonify()["required"], json!(["cursor", "text"]));
+    }
+
+    #[test]
+    fn test_export_schema() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Exported {
+            id: u64,
+        }
+        let path = std::env::temp_dir().join("ai-tools-ox-export/exported.json");
+        export_schema::<Exported>(&path).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written, Exported::jsonify());
+    }
+
+    #[test]
+    fn test_tuple_struct() {
+        #[allow(dead_code)]
+        #[derive(JsonifyObject)]
+        struct Range(
+            #[description(minimum = 0)] i32,
+            i32,
+            #[description(skip)] Option<String>,
+        );
+        assert_eq!(
+            Range::jsonify(),
+            json!({
+                "type": "array",
+                "prefixItems": [{"type": "integer", "minimum": 0}, {"type": "integer"}],
+                "minItems": 2,
+                "maxItems": 2,
+            })
+        );
+    }
 }
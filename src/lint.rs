@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::tools::{valid_name, Tool, MAX_DESCRIPTION_LENGTH, MAX_NAME_LENGTH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenAi,
+    Anthropic,
+    Gemini,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The provider rejects the tool.
+    Error,
+    /// The provider accepts the tool but may ignore part of it.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// JSON pointer into the tool definition, e.g. `/function/name`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity} at {}: {}", self.path, self.message)
+    }
+}
+
+const OPENAI_MAX_DEPTH: usize = 10;
+
+/// Keywords OpenAI's strict mode rejects.
+const OPENAI_STRICT_UNSUPPORTED: &[&str] = &[
+    "patternProperties",
+    "unevaluatedProperties",
+    "propertyNames",
+    "minProperties",
+    "maxProperties",
+    "unevaluatedItems",
+    "contains",
+    "minContains",
+    "maxContains",
+    "uniqueItems",
+];
+
+/// Keywords outside the OpenAPI subset Gemini accepts.
+const GEMINI_UNSUPPORTED: &[&str] = &[
+    "$ref",
+    "$defs",
+    "additionalProperties",
+    "const",
+    "oneOf",
+    "allOf",
+    "not",
+    "prefixItems",
+    "uniqueItems",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "examples",
+    "patternProperties",
+];
+
+impl Tool {
+    /// Checks the tool against the documented limits of `provider`, so that
+    /// problems show up before the API call fails.
+    pub fn validate_for(&self, provider: Provider) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let function = self.function();
+        if let Some(message) = name_error(&function.name, provider) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                path: "/function/name".to_string(),
+                message,
+            });
+        }
+        if function.description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                path: "/function/description".to_string(),
+                message: format!(
+                    "description is longer than {MAX_DESCRIPTION_LENGTH} characters and may be truncated"
+                ),
+            });
+        }
+        let parameters =
+            serde_json::to_value(&function.parameters).expect("parameters serialize to JSON");
+        let strict = function.strict == Some(true);
+        let mut linter = Linter {
+            provider,
+            strict,
+            diagnostics,
+        };
+        linter.schema(&parameters, "/function/parameters", 1);
+        linter.diagnostics
+    }
+}
+
+fn name_error(name: &str, provider: Provider) -> Option<String> {
+    // Gemini also accepts dots.
+    let (checked, allowed) = match provider {
+        Provider::Gemini => (name.replace('.', "_"), "a-z, A-Z, 0-9, _, - and ."),
+        _ => (name.to_string(), "a-z, A-Z, 0-9, _ and -"),
+    };
+    if !valid_name(&checked) {
+        return Some(format!(
+            "name must be 1 to {MAX_NAME_LENGTH} characters of {allowed}"
+        ));
+    }
+    let first = name.chars().next().unwrap();
+    if provider == Provider::Gemini && !(first.is_ascii_alphabetic() || first == '_') {
+        return Some("name must start with a letter or an underscore".to_string());
+    }
+    None
+}
+
+struct Linter {
+    provider: Provider,
+    strict: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn error(&mut self, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    fn schema(&mut self, schema: &serde_json::Value, path: &str, depth: usize) {
+        let Some(object) = schema.as_object() else {
+            return;
+        };
+        let unsupported = match self.provider {
+            Provider::OpenAi if self.strict => OPENAI_STRICT_UNSUPPORTED,
+            Provider::Gemini => GEMINI_UNSUPPORTED,
+            _ => &[],
+        };
+        for keyword in object.keys().filter(|k| unsupported.contains(&k.as_str())) {
+            self.error(path, format!("keyword `{keyword}` is not supported"));
+        }
+        if self.provider == Provider::Gemini && object.get("type").is_some_and(|t| t.is_array()) {
+            self.error(path, "type arrays are not supported".to_string());
+        }
+
+        let properties = object.get("properties").and_then(|p| p.as_object());
+        if let Some(properties) = properties {
+            if self.provider == Provider::OpenAi && depth > OPENAI_MAX_DEPTH {
+                self.error(
+                    path,
+                    format!("objects may be nested at most {OPENAI_MAX_DEPTH} levels deep"),
+                );
+            }
+            if self.provider == Provider::OpenAi && self.strict {
+                self.strict_object(object, properties, path);
+            }
+            for (name, property) in properties {
+                self.schema(property, &format!("{path}/properties/{name}"), depth + 1);
+            }
+        }
+        if let Some(items) = object.get("items") {
+            self.schema(items, &format!("{path}/items"), depth);
+        }
+        for keyword in ["anyOf", "oneOf", "allOf"] {
+            let Some(schemas) = object.get(keyword).and_then(|s| s.as_array()) else {
+                continue;
+            };
+            for (i, schema) in schemas.iter().enumerate() {
+                self.schema(schema, &format!("{path}/{keyword}/{i}"), depth);
+            }
+        }
+        if let Some(defs) = object.get("$defs").and_then(|d| d.as_object()) {
+            for (name, schema) in defs {
+                self.schema(schema, &format!("{path}/$defs/{name}"), depth);
+            }
+        }
+    }
+
+    /// Strict mode requires every object to be closed and every property to
+    /// be required.
+    fn strict_object(
+        &mut self,
+        object: &serde_json::Map<String, serde_json::Value>,
+        properties: &serde_json::Map<String, serde_json::Value>,
+        path: &str,
+    ) {
+        if object.get("additionalProperties") != Some(&serde_json::Value::Bool(false)) {
+            self.error(
+                path,
+                "strict mode requires `additionalProperties: false`".to_string(),
+            );
+        }
+        let required = object
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|n| n.as_str()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for name in properties
+            .keys()
+            .filter(|n| !required.contains(&n.as_str()))
+        {
+            self.error(
+                path,
+                format!("strict mode requires property `{name}` to be required"),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schema::Schema, tools::ToolBuilder};
+
+    #[test]
+    fn test_name_rules() {
+        let mut tool = ToolBuilder::new()
+            .name("weather")
+            .description("current weather")
+            .build()
+            .unwrap();
+        // `build` rejects names OpenAI does not accept.
+        tool.function_mut().name = "weather.current".to_string();
+        assert!(tool.validate_for(Provider::Gemini).is_empty());
+        let diagnostics = tool.validate_for(Provider::OpenAi);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "/function/name");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        let tool = ToolBuilder::new()
+            .name("1weather")
+            .description("current weather")
+            .build()
+            .unwrap();
+        assert!(tool.validate_for(Provider::Anthropic).is_empty());
+        assert_eq!(tool.validate_for(Provider::Gemini).len(), 1);
+    }
+
+    #[test]
+    fn test_keywords_and_strict_mode() {
+        let tool = ToolBuilder::new()
+            .name("publish")
+            .description("publishes an event")
+            .add_const_parameter("version", "payload version", "v2")
+            .add_parameter::<Vec<String>>("tags", "event tags")
+            .unique_items("tags")
+            .strict(true)
+            .build()
+            .unwrap();
+        assert!(tool.validate_for(Provider::Anthropic).is_empty());
+
+        let openai = tool.validate_for(Provider::OpenAi);
+        assert_eq!(
+            openai.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["error at /function/parameters/properties/tags: keyword `uniqueItems` is not supported"]
+        );
+
+        let gemini = tool.validate_for(Provider::Gemini);
+        let messages = gemini
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect::<Vec<_>>();
+        assert!(messages.contains(&"keyword `additionalProperties` is not supported"));
+        assert!(messages.contains(&"keyword `const` is not supported"));
+        assert!(messages.contains(&"keyword `uniqueItems` is not supported"));
+
+        let nested = crate::schema::SchemaBuilder::new()
+            .optional_property("city", Schema::string())
+            .build();
+        let tool = ToolBuilder::new()
+            .name("ship")
+            .description("ships a parcel")
+            .add_schema_parameter("address", "delivery address", nested)
+            .strict(true)
+            .build()
+            .unwrap();
+        assert!(tool.validate_for(Provider::OpenAi).is_empty());
+
+        let tool = |strict: bool, additional_properties: bool| {
+            Tool::try_from(serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": "ship",
+                    "description": "ships a parcel",
+                    "strict": strict,
+                    "parameters": {
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}},
+                        "required": [],
+                        "additionalProperties": additional_properties,
+                    },
+                },
+            }))
+            .unwrap()
+        };
+        let messages = tool(true, true)
+            .validate_for(Provider::OpenAi)
+            .into_iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "error at /function/parameters: strict mode requires `additionalProperties: false`",
+                "error at /function/parameters: strict mode requires property `city` to be required",
+            ]
+        );
+        assert!(tool(false, false).validate_for(Provider::OpenAi).is_empty());
+    }
+}
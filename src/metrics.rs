@@ -0,0 +1,75 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::tools::ToolError;
+
+/// Receives one observation per finished tool call, e.g. to feed counters
+/// and latency histograms of a metrics backend.
+pub trait ToolMetrics: fmt::Debug + Send + Sync {
+    fn record(&self, tool: &str, elapsed: Duration, error: Option<&ToolError>);
+}
+
+impl<M: ToolMetrics + ?Sized> ToolMetrics for Arc<M> {
+    fn record(&self, tool: &str, elapsed: Duration, error: Option<&ToolError>) {
+        (**self).record(tool, elapsed, error);
+    }
+}
+
+/// Tool name under which calls of unregistered tools are recorded, so that
+/// names made up by the model don't each get their own series.
+pub const UNKNOWN_TOOL: &str = "<unknown>";
+
+/// Upper bounds of the [`ToolStats::latency`] buckets.
+pub const LATENCY_BUCKETS: [Duration; 6] = [
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolStats {
+    pub calls: u64,
+    /// Failed calls by [`ToolError::kind`].
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Calls per [`LATENCY_BUCKETS`] bound, the last bucket counting the
+    /// slower ones.
+    pub latency: [u64; LATENCY_BUCKETS.len() + 1],
+    pub total_latency: Duration,
+}
+
+/// Keeps [`ToolStats`] per tool in memory.
+#[derive(Debug, Default)]
+pub struct MemoryMetrics(Mutex<HashMap<String, ToolStats>>);
+
+impl MemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn stats(&self, tool: &str) -> Option<ToolStats> {
+        self.0.lock().unwrap().get(tool).cloned()
+    }
+}
+
+impl ToolMetrics for MemoryMetrics {
+    fn record(&self, tool: &str, elapsed: Duration, error: Option<&ToolError>) {
+        let mut stats = self.0.lock().unwrap();
+        let stats = stats.entry(tool.to_string()).or_default();
+        stats.calls += 1;
+        if let Some(error) = error {
+            *stats.errors.entry(error.kind()).or_default() += 1;
+        }
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| elapsed <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        stats.latency[bucket] += 1;
+        stats.total_latency += elapsed;
+    }
+}
@@ -0,0 +1,564 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+
+use crate::{
+    context::ToolContext,
+    tools::{ToolCall, ToolCallResult, ToolError, Tools},
+};
+
+/// Wraps tool dispatch. Call [`Next::run`] to forward, or return early to
+/// short-circuit the tool. Errors are only rendered into a [`ToolCallResult`]
+/// once they leave the outermost middleware.
+#[async_trait::async_trait]
+pub trait ToolMiddleware: fmt::Debug + Send + Sync {
+    async fn around(&self, ctx: &ToolCall, next: Next<'_>) -> Result<ToolCallResult, ToolError>;
+}
+
+/// Middleware from a closure, see [`Tools::layer`].
+pub struct FnMiddleware<F>(F);
+
+/// Turns `f` into middleware. The closure returns a boxed future, e.g.
+/// `from_fn(|ctx, next| Box::pin(async move { next.run(ctx).await }))`.
+pub fn from_fn<F>(f: F) -> FnMiddleware<F>
+where
+    F: for<'a> Fn(&'a ToolCall, Next<'a>) -> BoxFuture<'a, Result<ToolCallResult, ToolError>>
+        + Send
+        + Sync,
+{
+    FnMiddleware(f)
+}
+
+impl<F> fmt::Debug for FnMiddleware<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnMiddleware").finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<F> ToolMiddleware for FnMiddleware<F>
+where
+    F: for<'a> Fn(&'a ToolCall, Next<'a>) -> BoxFuture<'a, Result<ToolCallResult, ToolError>>
+        + Send
+        + Sync,
+{
+    async fn around(&self, ctx: &ToolCall, next: Next<'_>) -> Result<ToolCallResult, ToolError> {
+        (self.0)(ctx, next).await
+    }
+}
+
+/// The remainder of the middleware chain, ending with the tool itself.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    tools: &'a Tools,
+    middleware: &'a [Arc<dyn ToolMiddleware>],
+    context: &'a ToolContext,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        tools: &'a Tools,
+        middleware: &'a [Arc<dyn ToolMiddleware>],
+        context: &'a ToolContext,
+    ) -> Self {
+        Self {
+            tools,
+            middleware,
+            context,
+        }
+    }
+    /// The context the call was made with.
+    pub fn context(&self) -> &'a ToolContext {
+        self.context
+    }
+    pub async fn run(self, tool_call: &ToolCall) -> Result<ToolCallResult, ToolError> {
+        match self.middleware.split_first() {
+            Some((middleware, rest)) => {
+                middleware
+                    .around(tool_call, Next::new(self.tools, rest, self.context))
+                    .await
+            }
+            None => self.tools.dispatch(tool_call, self.context).await,
+        }
+    }
+}
+
+/// Retries failed calls with exponential backoff. By default only
+/// [`ToolError::Failed`] and [`ToolError::TimedOut`] are retried, as the
+/// other errors would fail the same way again.
+#[derive(Clone)]
+pub struct Retry {
+    max_attempts: usize,
+    backoff: Duration,
+    multiplier: u32,
+    retry_on: Arc<dyn Fn(&ToolError) -> bool + Send + Sync>,
+    tools: Option<HashSet<String>>,
+}
+
+impl Retry {
+    /// Calls the tool up to `max_attempts` times, waiting 100ms before the
+    /// first retry and twice as long before each following one.
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            backoff: Duration::from_millis(100),
+            multiplier: 2,
+            retry_on: Arc::new(|e| matches!(e, ToolError::Failed(_) | ToolError::TimedOut(_))),
+            tools: None,
+        }
+    }
+    /// The wait before the first retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+    /// How much longer each wait is than the previous one.
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+    pub fn retry_on(
+        mut self,
+        retry_on: impl Fn(&ToolError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_on = Arc::new(retry_on);
+        self
+    }
+    /// Only retries calls of the given tools, the others pass through.
+    pub fn for_tools(mut self, names: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.tools = Some(names.into_iter().map(|name| name.to_string()).collect());
+        self
+    }
+}
+
+impl fmt::Debug for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retry")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("multiplier", &self.multiplier)
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolMiddleware for Retry {
+    async fn around(&self, ctx: &ToolCall, next: Next<'_>) -> Result<ToolCallResult, ToolError> {
+        if let Some(tools) = &self.tools {
+            if !tools.contains(&ctx.function.name) {
+                return next.run(ctx).await;
+            }
+        }
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match next.run(ctx).await {
+                Err(e) if attempt < self.max_attempts && (self.retry_on)(&e) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= self.multiplier;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+type CallerKey = dyn Fn(&ToolContext) -> String + Send + Sync;
+
+/// Buckets by tool and caller, and when full ones were last dropped.
+type Buckets = (HashMap<(String, String), (f64, Instant)>, Instant);
+
+/// Token bucket per tool name, refilled continuously: each bucket holds up
+/// to `capacity` calls and regains all of them over `period`. Calls over the
+/// limit fail with [`ToolError::RateLimited`] without running.
+pub struct RateLimit {
+    capacity: f64,
+    period: Duration,
+    caller: Option<Box<CallerKey>>,
+    tools: Option<HashSet<String>>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimit {
+    /// # Panics
+    ///
+    /// If `capacity` or `period` is zero, which would never let a call
+    /// through or never limit one.
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        assert!(capacity > 0, "rate limit capacity must be positive");
+        assert!(!period.is_zero(), "rate limit period must be positive");
+        Self {
+            capacity: capacity.into(),
+            period,
+            caller: None,
+            tools: None,
+            buckets: Mutex::new((HashMap::new(), Instant::now())),
+        }
+    }
+    /// Keeps a bucket per tool and caller, as returned by `caller` from the
+    /// call's [`ToolContext`]. The arguments are chosen by the model and
+    /// must not decide whose budget a call draws from.
+    pub fn per_caller(
+        mut self,
+        caller: impl Fn(&ToolContext) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.caller = Some(Box::new(caller));
+        self
+    }
+    /// Only limits the given tools, the others pass through.
+    pub fn for_tools(mut self, names: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.tools = Some(names.into_iter().map(|name| name.to_string()).collect());
+        self
+    }
+    /// Takes a token from the bucket of `key`, or returns how long until
+    /// the next one.
+    fn acquire(&self, key: (String, String)) -> Result<(), Duration> {
+        let now = Instant::now();
+        let rate = self.capacity / self.period.as_secs_f64();
+        let refill = |(tokens, updated): &(f64, Instant)| {
+            (tokens + now.duration_since(*updated).as_secs_f64() * rate).min(self.capacity)
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        let (buckets, swept) = &mut *buckets;
+        // A full bucket is the same as none, drop them once per period so
+        // one-off callers don't accumulate.
+        if now.duration_since(*swept) >= self.period {
+            buckets.retain(|_, bucket| refill(bucket) < self.capacity);
+            *swept = now;
+        }
+        let bucket = buckets.entry(key).or_insert((self.capacity, now));
+        *bucket = (refill(bucket), now);
+        let (tokens, _) = bucket;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / rate))
+        }
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("capacity", &self.capacity)
+            .field("period", &self.period)
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl ToolMiddleware for RateLimit {
+    async fn around(&self, ctx: &ToolCall, next: Next<'_>) -> Result<ToolCallResult, ToolError> {
+        let name = &ctx.function.name;
+        if self.tools.as_ref().is_none_or(|tools| tools.contains(name)) {
+            let caller = self
+                .caller
+                .as_ref()
+                .map(|caller| caller(next.context()))
+                .unwrap_or_default();
+            if let Err(retry_after) = self.acquire((name.clone(), caller)) {
+                return Err(ToolError::RateLimited {
+                    name: name.clone(),
+                    retry_after,
+                });
+            }
+        }
+        next.run(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use super::*;
+    use crate::tools::{ToTool, Tool, ToolBuilder, ToolCallFunction, ToolType};
+
+    #[derive(Debug, Default)]
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl ToTool for Counter {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("counter")
+                .description("counts calls")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            match input["fail"].as_bool() {
+                Some(true) => Err(ToolError::Failed("downstream returned 500".to_string())),
+                _ => Ok(ToolCallResult::text(id, "ok")),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Deny(&'static str);
+
+    #[async_trait::async_trait]
+    impl ToolMiddleware for Deny {
+        async fn around(
+            &self,
+            ctx: &ToolCall,
+            next: Next<'_>,
+        ) -> Result<ToolCallResult, ToolError> {
+            if ctx.function.name == self.0 {
+                Ok(ToolCallResult::text(&ctx.id, "denied"))
+            } else {
+                next.run(ctx).await
+            }
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct ErrorCounter(Mutex<HashMap<&'static str, usize>>);
+
+    #[async_trait::async_trait]
+    impl ToolMiddleware for Arc<ErrorCounter> {
+        async fn around(
+            &self,
+            ctx: &ToolCall,
+            next: Next<'_>,
+        ) -> Result<ToolCallResult, ToolError> {
+            let result = next.run(ctx).await;
+            if let Err(e) = &result {
+                let kind = match e {
+                    ToolError::NotFound(_) => "not_found",
+                    ToolError::InvalidArguments(_) => "invalid_arguments",
+                    ToolError::Failed(_) => "failed",
+                    ToolError::TimedOut(_) => "timed_out",
+                    ToolError::Cancelled => "cancelled",
+                    ToolError::Disabled(_) => "disabled",
+                    ToolError::NotAllowed(_) => "not_allowed",
+                    ToolError::RateLimited { .. } => "rate_limited",
+                };
+                *self.0.lock().unwrap().entry(kind).or_default() += 1;
+            }
+            result
+        }
+    }
+
+    fn tool_call(name: &str) -> ToolCall {
+        tool_call_with(name, "{}")
+    }
+
+    fn tool_call_with(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            tool_type: ToolType::Function,
+            function: ToolCallFunction {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_short_circuits() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Counter(calls.clone()))
+            .with_middleware(Deny("counter"));
+
+        let results = tools.call_tools(&[tool_call("counter")]).await;
+        assert_eq!(results.0[0].to_text(), "denied");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_forwards() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Counter(calls.clone()))
+            .with_middleware(Deny("other"));
+
+        let results = tools.call_tools(&[tool_call("counter")]).await;
+        assert_eq!(results.0[0].to_text(), "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_observes_errors() {
+        let errors = Arc::new(ErrorCounter::default());
+        let tools = Tools::default()
+            .add_tool(Counter::default())
+            .with_middleware(errors.clone());
+
+        let results = tools
+            .call_tools(&[
+                tool_call("counter"),
+                tool_call("missing"),
+                tool_call_with("counter", "not json"),
+                tool_call_with("counter", r#"{"fail": true}"#),
+                tool_call_with("counter", r#"{"fail": true}"#),
+            ])
+            .await;
+
+        let errors = errors.0.lock().unwrap();
+        assert_eq!(errors.get("not_found"), Some(&1));
+        assert_eq!(errors.get("invalid_arguments"), Some(&1));
+        assert_eq!(errors.get("failed"), Some(&2));
+        assert_eq!(
+            results.0[3].to_text(),
+            "Tool failed: downstream returned 500"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        #[derive(Debug, Default)]
+        struct Flaky(AtomicUsize);
+
+        #[async_trait::async_trait]
+        impl ToTool for Flaky {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("flaky")
+                    .description("fails twice")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                match self.0.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(ToolError::Failed("connection reset".to_string())),
+                    _ => Ok(ToolCallResult::text(id, "ok")),
+                }
+            }
+        }
+
+        let tools = Tools::default()
+            .add_tool(Flaky::default())
+            .with_middleware(Retry::new(3).backoff(Duration::from_millis(1)));
+        let results = tools.call_tools(&[tool_call("flaky")]).await;
+        assert_eq!(results.0[0].to_text(), "ok");
+
+        let tools = Tools::default()
+            .add_tool(Flaky::default())
+            .with_middleware(Retry::new(2).backoff(Duration::from_millis(1)));
+        let results = tools.call_tools(&[tool_call("flaky")]).await;
+        assert_eq!(results.0[0].to_text(), "Tool failed: connection reset");
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Counter(calls.clone()))
+            .with_middleware(
+                Retry::new(3)
+                    .backoff(Duration::from_millis(1))
+                    .for_tools(["other"]),
+            );
+        let results = tools
+            .call_tools(&[tool_call_with("counter", r#"{"fail": true}"#)])
+            .await;
+        assert_eq!(
+            results.0[0].to_text(),
+            "Tool failed: downstream returned 500"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        let tools = Tools::default()
+            .add_tool(Counter::default())
+            .layer(|ctx, next| {
+                Box::pin(async move {
+                    if ctx.function.arguments.contains("token") {
+                        next.run(ctx).await
+                    } else {
+                        Ok(ToolCallResult::text(&ctx.id, "unauthorized"))
+                    }
+                })
+            })
+            .layer(|ctx, next| {
+                Box::pin(async move {
+                    let mut result = next.run(ctx).await?;
+                    result.push_text("checked");
+                    Ok(result)
+                })
+            });
+
+        let results = tools
+            .call_tools(&[
+                tool_call("counter"),
+                tool_call_with("counter", r#"{"token": "secret"}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "unauthorized");
+        assert_eq!(results.0[1].to_text(), "ok\nchecked");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Counter(calls.clone()))
+            .with_middleware(
+                RateLimit::new(2, Duration::from_secs(3600))
+                    .per_caller(|context| context.get::<String>().cloned().unwrap_or_default()),
+            );
+
+        let calls_by = |user: &str| {
+            let context = ToolContext::new().with(user.to_string());
+            let tools = &tools;
+            async move {
+                tools
+                    .call_tools_with_context(&[tool_call_with("counter", "{}")], &context)
+                    .await
+            }
+        };
+        let results = [
+            calls_by("a").await,
+            calls_by("a").await,
+            calls_by("a").await,
+            calls_by("b").await,
+        ];
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(results[1].0[0].to_text(), "ok");
+        assert!(results[2].0[0]
+            .to_text()
+            .starts_with("Tool counter is rate limited, try again in "));
+        assert_eq!(results[3].0[0].to_text(), "ok");
+
+        let limit = RateLimit::new(1, Duration::from_millis(10));
+        assert!(limit.acquire(("t".to_string(), String::new())).is_ok());
+        assert!(limit.acquire(("t".to_string(), String::new())).is_err());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(limit.acquire(("t".to_string(), String::new())).is_ok());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(limit.acquire(("u".to_string(), String::new())).is_ok());
+        let buckets = limit.buckets.lock().unwrap();
+        assert!(!buckets.0.contains_key(&("t".to_string(), String::new())));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be positive")]
+    fn test_rate_limit_zero_capacity() {
+        RateLimit::new(0, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(feature = "schemars")]
+use std::marker::PhantomData;
+
+use serde_json::json;
+
+use crate::Jsonify;
+#[cfg(feature = "schemars")]
+use crate::JsonifyConfig;
+
+/// A JSON Schema built from Rust types and combinators, for parameters that
+/// accept alternatives such as "string or number".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema(serde_json::Value);
+
+impl Schema {
+    pub fn of<T: Jsonify>() -> Self {
+        Self(T::jsonify())
+    }
+    pub fn string() -> Self {
+        Self(json!({ "type": "string" }))
+    }
+    pub fn integer() -> Self {
+        Self(json!({ "type": "integer" }))
+    }
+    pub fn number() -> Self {
+        Self(json!({ "type": "number" }))
+    }
+    pub fn boolean() -> Self {
+        Self(json!({ "type": "boolean" }))
+    }
+    pub fn array(items: Schema) -> Self {
+        Self(json!({ "type": "array", "items": items.0 }))
+    }
+    /// A string that must be one of `values`.
+    pub fn enumeration(values: impl IntoIterator<Item = impl ToString>) -> Self {
+        let values = values
+            .into_iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>();
+        Self(json!({ "type": "string", "enum": values }))
+    }
+    /// Sets any keyword, e.g. `.with("minimum", 0)`.
+    pub fn with(mut self, keyword: &str, value: impl Into<serde_json::Value>) -> Self {
+        if let Some(schema) = self.0.as_object_mut() {
+            schema.insert(keyword.to_string(), value.into());
+        }
+        self
+    }
+    pub fn description(self, description: impl ToString) -> Self {
+        self.with("description", description.to_string())
+    }
+    /// Accepts exactly `value`.
+    pub fn constant(value: impl Into<serde_json::Value>) -> Self {
+        Self(json!({ "const": value.into() }))
+    }
+    pub fn any_of(schemas: impl IntoIterator<Item = Schema>) -> Self {
+        Self::combine("anyOf", schemas)
+    }
+    pub fn one_of(schemas: impl IntoIterator<Item = Schema>) -> Self {
+        Self::combine("oneOf", schemas)
+    }
+    pub fn all_of(schemas: impl IntoIterator<Item = Schema>) -> Self {
+        Self::combine("allOf", schemas)
+    }
+    fn combine(keyword: &str, schemas: impl IntoIterator<Item = Schema>) -> Self {
+        let schemas = schemas
+            .into_iter()
+            .map(Schema::into_value)
+            .collect::<Vec<_>>();
+        Self(json!({ keyword: schemas }))
+    }
+    pub fn as_value(&self) -> &serde_json::Value {
+        &self.0
+    }
+    pub fn into_value(self) -> serde_json::Value {
+        self.0
+    }
+}
+
+impl From<serde_json::Value> for Schema {
+    fn from(schema: serde_json::Value) -> Self {
+        Self(schema)
+    }
+}
+
+impl From<Schema> for serde_json::Value {
+    fn from(schema: Schema) -> Self {
+        schema.0
+    }
+}
+
+/// Builds an object schema at runtime, for parameters only known from
+/// configuration.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaBuilder {
+    properties: serde_json::Map<String, serde_json::Value>,
+    required: Vec<String>,
+    strict: bool,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn property(mut self, name: impl ToString, schema: Schema) -> Self {
+        self.required.push(name.to_string());
+        self.optional_property(name, schema)
+    }
+    pub fn optional_property(mut self, name: impl ToString, schema: Schema) -> Self {
+        self.properties.insert(name.to_string(), schema.0);
+        self
+    }
+    /// Rejects properties that were not declared.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+    pub fn build(self) -> Schema {
+        let mut schema = json!({
+            "type": "object",
+            "properties": self.properties,
+            "required": self.required,
+        });
+        if self.strict {
+            schema["additionalProperties"] = json!(false);
+        }
+        Schema(schema)
+    }
+}
+
+/// The schema of a `schemars::JsonSchema` type, usable wherever a `Jsonify`
+/// type is, e.g. `add_parameter::<Schemars<Filter>>(...)`. The
+/// `JsonifyConfig` does not apply, schemars decides the output.
+#[cfg(feature = "schemars")]
+pub struct Schemars<T: ?Sized>(PhantomData<T>);
+
+#[cfg(feature = "schemars")]
+impl<T: schemars::JsonSchema + ?Sized> Jsonify for Schemars<T> {
+    fn jsonify_with(_config: &JsonifyConfig) -> serde_json::Value {
+        let mut schema = schemars::schema_for!(T).to_value();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.remove("$schema");
+        }
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combinators() {
+        let schema = Schema::any_of([Schema::of::<String>(), Schema::of::<f64>()]);
+        assert_eq!(
+            schema.into_value(),
+            json!({"anyOf": [{"type": "string"}, {"type": "number"}]})
+        );
+        let schema = Schema::one_of([
+            Schema::of::<bool>(),
+            Schema::all_of([
+                json!({"type": "integer"}).into(),
+                json!({"minimum": 0}).into(),
+            ]),
+        ]);
+        assert_eq!(
+            schema.as_value(),
+            &json!({
+                "oneOf": [
+                    {"type": "boolean"},
+                    {"allOf": [{"type": "integer"}, {"minimum": 0}]},
+                ]
+            })
+        );
+        assert_eq!(Schema::constant("v2").into_value(), json!({"const": "v2"}));
+    }
+
+    #[test]
+    fn test_schema_builder() {
+        let address = SchemaBuilder::new()
+            .property("city", Schema::string())
+            .optional_property("zip", Schema::string().with("pattern", "^[0-9]{5}$"))
+            .strict()
+            .build();
+        let schema = SchemaBuilder::new()
+            .property("tags", Schema::array(Schema::string()).with("maxItems", 5))
+            .property("unit", Schema::enumeration(["metric", "imperial"]))
+            .optional_property("address", address.description("delivery address"))
+            .build();
+        assert_eq!(
+            schema.into_value(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 5},
+                    "unit": {"type": "string", "enum": ["metric", "imperial"]},
+                    "address": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                            "zip": {"type": "string", "pattern": "^[0-9]{5}$"},
+                        },
+                        "required": ["city"],
+                        "additionalProperties": false,
+                        "description": "delivery address",
+                    },
+                },
+                "required": ["tags", "unit"],
+            })
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use serde_json::Value;
+
+use crate::tools::Tools;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    ToolAdded(String),
+    ToolRemoved(String),
+    DescriptionChanged {
+        tool: String,
+        before: Value,
+        after: Value,
+    },
+    PropertyAdded {
+        tool: String,
+        property: String,
+    },
+    PropertyRemoved {
+        tool: String,
+        property: String,
+    },
+    PropertyChanged {
+        tool: String,
+        property: String,
+        before: Value,
+        after: Value,
+    },
+    RequiredChanged {
+        tool: String,
+        before: Value,
+        after: Value,
+    },
+}
+
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(sort_keys).collect()),
+        value => value.clone(),
+    }
+}
+
+impl Tools {
+    /// Tool schemas keyed by name with all object keys sorted, so equal
+    /// registries always produce equal snapshots.
+    pub fn snapshot(&self) -> String {
+        let tools = self
+            .definitions()
+            .map(|(name, json)| (name.to_string(), json.clone()))
+            .collect();
+        serde_json::to_string_pretty(&sort_keys(&Value::Object(tools))).unwrap()
+    }
+    /// Changes from the `previous` snapshot to the current registry.
+    pub fn diff(&self, previous: &str) -> Result<Vec<SchemaChange>, serde_json::Error> {
+        let previous: Value = serde_json::from_str(previous)?;
+        let current: Value = serde_json::from_str(&self.snapshot())?;
+        let empty = serde_json::Map::new();
+        let previous = previous.as_object().unwrap_or(&empty);
+        let current = current.as_object().unwrap_or(&empty);
+
+        let mut names = previous.keys().chain(current.keys()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        let mut changes = Vec::new();
+        for name in names {
+            let (before, after) = match (previous.get(name), current.get(name)) {
+                (Some(before), Some(after)) => (&before["function"], &after["function"]),
+                (Some(_), None) => {
+                    changes.push(SchemaChange::ToolRemoved(name.clone()));
+                    continue;
+                }
+                _ => {
+                    changes.push(SchemaChange::ToolAdded(name.clone()));
+                    continue;
+                }
+            };
+            if before["description"] != after["description"] {
+                changes.push(SchemaChange::DescriptionChanged {
+                    tool: name.clone(),
+                    before: before["description"].clone(),
+                    after: after["description"].clone(),
+                });
+            }
+            let properties = |function: &Value| {
+                function["parameters"]["properties"]
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let (before_properties, after_properties) = (properties(before), properties(after));
+            let mut keys = before_properties
+                .keys()
+                .chain(after_properties.keys())
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for property in keys {
+                let tool = name.clone();
+                match (
+                    before_properties.get(&property),
+                    after_properties.get(&property),
+                ) {
+                    (Some(before), Some(after)) if before != after => {
+                        changes.push(SchemaChange::PropertyChanged {
+                            tool,
+                            property,
+                            before: before.clone(),
+                            after: after.clone(),
+                        })
+                    }
+                    (Some(_), None) => {
+                        changes.push(SchemaChange::PropertyRemoved { tool, property })
+                    }
+                    (None, Some(_)) => changes.push(SchemaChange::PropertyAdded { tool, property }),
+                    _ => {}
+                }
+            }
+            let (before_required, after_required) = (
+                &before["parameters"]["required"],
+                &after["parameters"]["required"],
+            );
+            if before_required != after_required {
+                changes.push(SchemaChange::RequiredChanged {
+                    tool: name.clone(),
+                    before: before_required.clone(),
+                    after: after_required.clone(),
+                });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tools::{ToTool, Tool, ToolBuilder, ToolCallResult, ToolError};
+
+    #[derive(Debug)]
+    struct Search(&'static str);
+
+    #[async_trait::async_trait]
+    impl ToTool for Search {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("search")
+                .description(self.0)
+                .add_parameter::<String>("query", "search query")
+                .add_optional_parameter::<i32>("limit", "max results")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            _input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            Ok(ToolCallResult::text(id, "[]"))
+        }
+    }
+
+    #[test]
+    fn test_snapshot_is_stable() {
+        let tools = Tools::default().add_tool(Search("searches"));
+        let snapshot = tools.snapshot();
+        assert_eq!(snapshot, tools.snapshot());
+        assert!(tools.diff(&snapshot).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_description_change() {
+        let previous = Tools::default().add_tool(Search("searches")).snapshot();
+        let tools = Tools::default().add_tool(Search("searches the web"));
+
+        assert_eq!(
+            tools.diff(&previous).unwrap(),
+            [SchemaChange::DescriptionChanged {
+                tool: "search".to_string(),
+                before: json!("searches"),
+                after: json!("searches the web"),
+            }]
+        );
+        assert_eq!(
+            Tools::default().diff(&previous).unwrap(),
+            [SchemaChange::ToolRemoved("search".to_string())]
+        );
+    }
+}
@@ -1,25 +1,56 @@
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    sync::{Arc, LazyLock, OnceLock, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
 
 use derivative::Derivative;
+use futures::future::join_all;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error;
+use tokio::sync::Semaphore;
+pub use tokio_util::sync::CancellationToken;
 
-use crate::Jsonify;
+use crate::{
+    args::FromToolArgs,
+    audit::{AuditContext, AuditRecord, AuditSink},
+    cache::{normalize_arguments, ToolCache},
+    context::ToolContext,
+    metrics::ToolMetrics,
+    middleware::{from_fn, Next, ToolMiddleware},
+    schema::Schema,
+    validate::{validate, SchemaViolation},
+    EnumJsonify, Jsonify, JsonifyConfig,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ToolType {
+    #[default]
     Function,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolParameter {
-    #[serde(rename = "type")]
-    argument_type: String,
+    #[serde(flatten)]
+    schema: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     description: String,
     #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
     argument_enum: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deprecated: bool,
+    #[serde(
+        rename = "x-sensitive",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    sensitive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
@@ -28,22 +59,164 @@ pub struct ToolParameters {
     #[serde(rename = "type")]
     #[derivative(Default(value = "String::from(\"object\")"))]
     parameter_type: String,
-    properties: HashMap<String, ToolParameter>,
+    properties: IndexMap<String, ToolParameter>,
     required: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    deprecated: bool,
+    #[serde(rename = "oneOf", skip_serializing_if = "Option::is_none")]
+    one_of: Option<Vec<serde_json::Value>>,
+    #[serde(
+        rename = "additionalProperties",
+        skip_serializing_if = "Option::is_none"
+    )]
+    additional_properties: Option<bool>,
+    /// Definitions of recursive parameter types, hoisted from the parameter
+    /// schemas so that their `#/$defs/...` references resolve.
+    #[serde(rename = "$defs", skip_serializing_if = "Option::is_none")]
+    defs: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolFunction {
     pub name: String,
     pub description: String,
     pub parameters: ToolParameters,
+    /// OpenAI's structured outputs mode, see [`ToolBuilder::strict`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// A tool definition. Built with [`ToolBuilder`] or [`Tool::new`], the
+/// fields are behind accessors so that the cached JSON stays current.
+#[derive(Clone, Serialize, Deserialize, Derivative)]
+#[derivative(Debug)]
 pub struct Tool {
-    #[serde(rename = "type")]
-    pub tool_type: ToolType,
-    pub function: ToolFunction,
+    #[serde(rename = "type", default)]
+    tool_type: ToolType,
+    function: ToolFunction,
+    #[serde(skip)]
+    #[derivative(Debug = "ignore")]
+    json: OnceLock<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaDialect {
+    Draft07,
+    Draft201909,
+    #[default]
+    Draft202012,
+}
+
+impl SchemaDialect {
+    pub fn uri(&self) -> &'static str {
+        match self {
+            SchemaDialect::Draft07 => "http://json-schema.org/draft-07/schema#",
+            SchemaDialect::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+            SchemaDialect::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+}
+
+impl Tool {
+    pub fn new(function: ToolFunction) -> Self {
+        Self {
+            tool_type: ToolType::Function,
+            function,
+            json: OnceLock::new(),
+        }
+    }
+    pub fn tool_type(&self) -> &ToolType {
+        &self.tool_type
+    }
+    pub fn function(&self) -> &ToolFunction {
+        &self.function
+    }
+    /// Mutable access to the function, discarding the cached JSON.
+    pub fn function_mut(&mut self) -> &mut ToolFunction {
+        self.json.take();
+        &mut self.function
+    }
+    /// The tool as JSON, serialized on the first successful call only.
+    pub fn to_value(&self) -> Result<&serde_json::Value, serde_json::Error> {
+        if let Some(json) = self.json.get() {
+            return Ok(json);
+        }
+        let json = serde_json::to_value(self)?;
+        Ok(self.json.get_or_init(|| json))
+    }
+    /// The tool in the format of OpenAI's `tools` array, which is how a
+    /// `Tool` serializes. Cached like [`Self::to_value`].
+    pub fn to_openai_value(&self) -> Result<&serde_json::Value, serde_json::Error> {
+        self.to_value()
+    }
+    /// The parameter schema as a standalone JSON Schema document.
+    pub fn to_schema_document(&self, dialect: SchemaDialect) -> serde_json::Value {
+        let schema =
+            serde_json::to_value(&self.function.parameters).expect("parameters serialize to JSON");
+        let mut document = json!({ "$schema": dialect.uri() });
+        if let (Some(document), serde_json::Value::Object(schema)) =
+            (document.as_object_mut(), schema)
+        {
+            document.extend(schema);
+        }
+        document
+    }
+    pub fn to_schema_document_with_id(
+        &self,
+        dialect: SchemaDialect,
+        id: impl ToString,
+    ) -> serde_json::Value {
+        let mut document = self.to_schema_document(dialect);
+        document["$id"] = json!(id.to_string());
+        document
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ToolValidationError {
+    #[error("Tool type must be \"function\", got {0}")]
+    UnsupportedType(serde_json::Value),
+    #[error("Tool has no function object")]
+    MissingFunction,
+    #[error("Parameters must be an object schema with properties")]
+    ParametersNotObject,
+    #[error("Required parameter {0} is not in properties")]
+    UnknownRequired(String),
+    #[error("Required parameter names must be strings, got {0}")]
+    RequiredNotString(serde_json::Value),
+    #[error("Invalid tool: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+impl TryFrom<serde_json::Value> for Tool {
+    type Error = ToolValidationError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        if !value["type"].is_null() && value["type"] != "function" {
+            return Err(ToolValidationError::UnsupportedType(value["type"].clone()));
+        }
+        let function = value
+            .get("function")
+            .filter(|function| function.is_object())
+            .ok_or(ToolValidationError::MissingFunction)?;
+        let parameters = &function["parameters"];
+        let properties = parameters
+            .get("properties")
+            .and_then(serde_json::Value::as_object)
+            .filter(|_| parameters["type"] == "object")
+            .ok_or(ToolValidationError::ParametersNotObject)?;
+        if let Some(required) = parameters["required"].as_array() {
+            for name in required {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| ToolValidationError::RequiredNotString(name.clone()))?;
+                if !properties.contains_key(name) {
+                    return Err(ToolValidationError::UnknownRequired(name.to_string()));
+                }
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,68 +233,586 @@ pub struct ToolCall {
     pub function: ToolCallFunction,
 }
 
+/// Longest tool name OpenAI accepts.
+pub(crate) const MAX_NAME_LENGTH: usize = 64;
+/// Longest tool description OpenAI accepts, in characters.
+pub(crate) const MAX_DESCRIPTION_LENGTH: usize = 1024;
+/// Largest serialized parameter schema accepted by [`ToolBuilder::build`],
+/// in bytes.
+pub(crate) const MAX_SCHEMA_SIZE: usize = 64 * 1024;
+
 #[derive(Debug, Error)]
 pub enum ToolBuilderError {
-    #[error("Name not set")]
-    NameNotSet,
-    #[error("Description not set")]
-    DescriptionNotSet,
+    #[error("Unknown parameter: {0}")]
+    UnknownParameter(String),
+    #[error("Default of parameter {name} cannot be serialized: {source}")]
+    InvalidDefault {
+        name: String,
+        source: serde_json::Error,
+    },
+    #[error("Invalid tool name {0:?}: expected 1 to {MAX_NAME_LENGTH} of a-z, A-Z, 0-9, _ and -")]
+    InvalidName(String),
+    #[error("Description is {0} characters long, at most {MAX_DESCRIPTION_LENGTH} are allowed")]
+    DescriptionTooLong(usize),
+    #[error("Parameter schema is {0} bytes long, at most {MAX_SCHEMA_SIZE} are allowed")]
+    SchemaTooLarge(usize),
+}
+
+/// Whether `name` matches `^[a-zA-Z0-9_-]{1,64}$`.
+pub(crate) const fn valid_name(name: &str) -> bool {
+    let name = name.as_bytes();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return false;
+    }
+    let mut i = 0;
+    while i < name.len() {
+        let c = name[i];
+        if !(c.is_ascii_alphanumeric() || c == b'_' || c == b'-') {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// The number of characters in `text`, usable in const contexts.
+const fn char_count(text: &str) -> usize {
+    let text = text.as_bytes();
+    let (mut count, mut i) = (0, 0);
+    while i < text.len() {
+        // Continuation bytes of multi-byte characters look like `10xxxxxx`.
+        if text[i] & 0xC0 != 0x80 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Panics if [`ToolBuilder::build`] would reject `name` or `description`.
+/// Evaluated in a const, so that invalid definitions fail to compile.
+#[doc(hidden)]
+pub const fn assert_definition(name: &str, description: &str) {
+    assert!(
+        valid_name(name),
+        "invalid tool name: expected 1 to 64 of a-z, A-Z, 0-9, _ and -"
+    );
+    assert!(
+        char_count(description) <= MAX_DESCRIPTION_LENGTH,
+        "tool descriptions are at most 1024 characters long"
+    );
+}
+
+fn schema_of<T: Jsonify>() -> serde_json::Map<String, serde_json::Value> {
+    schema_map(T::jsonify())
+}
+
+fn schema_map(schema: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    match schema {
+        serde_json::Value::Object(schema) => schema,
+        schema => serde_json::Map::from_iter([("type".to_string(), schema)]),
+    }
+}
+
+/// Closes every object schema nested in `schema` and requires all of its
+/// properties, as strict mode expects. Formerly optional properties accept
+/// `null` instead of being left out.
+fn strict_schema(schema: &mut serde_json::Value) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    let required = object
+        .get("required")
+        .and_then(serde_json::Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    if let Some(properties) = object
+        .get_mut("properties")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        let config = JsonifyConfig::default();
+        for (name, property) in properties.iter_mut() {
+            strict_schema(property);
+            if !required.contains(&json!(name)) {
+                *property = config.or_null(std::mem::take(property));
+            }
+        }
+        let required = properties.keys().cloned().collect::<Vec<_>>();
+        object.insert("required".to_string(), json!(required));
+        object.insert("additionalProperties".to_string(), json!(false));
+    }
+    for keyword in ["items", "additionalProperties", "not"] {
+        if let Some(schema) = object.get_mut(keyword) {
+            strict_schema(schema);
+        }
+    }
+    for keyword in ["prefixItems", "anyOf", "oneOf", "allOf"] {
+        if let Some(schemas) = object
+            .get_mut(keyword)
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            schemas.iter_mut().for_each(strict_schema);
+        }
+    }
+    for keyword in ["$defs", "definitions"] {
+        if let Some(schemas) = object
+            .get_mut(keyword)
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            schemas.values_mut().for_each(strict_schema);
+        }
+    }
+}
+
+/// Constraints of an array parameter and its items, see
+/// [`ToolBuilder::add_array_parameter`].
+#[derive(Debug, Default)]
+pub struct ArrayParameter {
+    keywords: serde_json::Map<String, serde_json::Value>,
+    items: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ArrayParameter {
+    pub fn min_items(mut self, items: u64) -> Self {
+        self.keywords.insert("minItems".to_string(), json!(items));
+        self
+    }
+    pub fn max_items(mut self, items: u64) -> Self {
+        self.keywords.insert("maxItems".to_string(), json!(items));
+        self
+    }
+    pub fn unique_items(mut self) -> Self {
+        self.keywords.insert("uniqueItems".to_string(), json!(true));
+        self
+    }
+    /// Adds `keyword` to the schema of every item, e.g. `minLength`.
+    pub fn item(mut self, keyword: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.items.insert(keyword.to_string(), value.into());
+        self
+    }
+}
+
+/// Constraints of a map parameter and its values, see
+/// [`ToolBuilder::add_map_parameter`].
+#[derive(Debug, Default)]
+pub struct MapParameter {
+    keywords: serde_json::Map<String, serde_json::Value>,
+    values: serde_json::Map<String, serde_json::Value>,
+}
+
+impl MapParameter {
+    pub fn min_properties(mut self, properties: u64) -> Self {
+        self.keywords
+            .insert("minProperties".to_string(), json!(properties));
+        self
+    }
+    pub fn max_properties(mut self, properties: u64) -> Self {
+        self.keywords
+            .insert("maxProperties".to_string(), json!(properties));
+        self
+    }
+    /// Adds `keyword` to the schema of every value, e.g. `minimum`.
+    pub fn value(mut self, keyword: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.values.insert(keyword.to_string(), value.into());
+        self
+    }
+}
+
+/// Merges `keywords` into the object schema at `schema[key]`.
+fn constrain_nested(
+    schema: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    keywords: serde_json::Map<String, serde_json::Value>,
+) {
+    if let Some(serde_json::Value::Object(nested)) = schema.get_mut(key) {
+        nested.extend(keywords);
+    }
 }
 
-#[derive(Default)]
-pub struct ToolBuilder {
-    name: Option<String>,
-    description: Option<String>,
+/// [`ToolBuilder`] state before `name` is called.
+#[derive(Debug)]
+pub struct NoName;
+/// [`ToolBuilder`] state after `name` is called.
+#[derive(Debug)]
+pub struct Named;
+/// [`ToolBuilder`] state before `description` is called.
+#[derive(Debug)]
+pub struct NoDescription;
+/// [`ToolBuilder`] state after `description` is called.
+#[derive(Debug)]
+pub struct Described;
+
+/// Builds a [`Tool`]. `build` is only available once both the name and the
+/// description are set.
+pub struct ToolBuilder<N = NoName, D = NoDescription> {
+    name: String,
+    description: String,
     parameters: Option<ToolParameters>,
+    unknown_parameter: Option<String>,
+    invalid_default: Option<(String, serde_json::Error)>,
+    strict: Option<bool>,
+    state: PhantomData<(N, D)>,
 }
 
 impl ToolBuilder {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            name: String::new(),
+            description: String::new(),
+            parameters: None,
+            unknown_parameter: None,
+            invalid_default: None,
+            strict: None,
+            state: PhantomData,
+        }
     }
-    pub fn name(mut self, name: &str) -> Self {
-        self.name = Some(name.to_string());
-        self
+}
+
+impl Default for ToolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, D> ToolBuilder<N, D> {
+    fn with_state<M, E>(self) -> ToolBuilder<M, E> {
+        ToolBuilder {
+            name: self.name,
+            description: self.description,
+            parameters: self.parameters,
+            unknown_parameter: self.unknown_parameter,
+            invalid_default: self.invalid_default,
+            strict: self.strict,
+            state: PhantomData,
+        }
+    }
+    pub fn name(mut self, name: &str) -> ToolBuilder<Named, D> {
+        self.name = name.to_string();
+        self.with_state()
     }
-    pub fn description(mut self, description: &str) -> Self {
-        self.description = Some(description.to_string());
+    pub fn description(mut self, description: &str) -> ToolBuilder<N, Described> {
+        self.description = description.to_string();
+        self.with_state()
+    }
+    fn insert_parameter(
+        mut self,
+        name: impl ToString,
+        mut argument: ToolParameter,
+        required: bool,
+    ) -> Self {
+        let mut arguments = self.parameters.unwrap_or_default();
+        // The parameter's own description replaces the type's doc comment.
+        argument.schema.remove("description");
+        if let Some(serde_json::Value::Object(defs)) = argument.schema.remove("$defs") {
+            arguments
+                .defs
+                .get_or_insert_with(Default::default)
+                .extend(defs);
+        }
+        arguments.properties.insert(name.to_string(), argument);
+        if required {
+            arguments.required.push(name.to_string());
+        }
+        self.parameters = Some(arguments);
+
         self
     }
     pub fn add_parameter<T: Jsonify>(
-        mut self,
+        self,
         name: impl ToString,
         description: impl ToString,
     ) -> Self {
         let argument = ToolParameter {
-            argument_type: T::jsonify().as_str().unwrap().to_string(),
+            schema: schema_of::<T>(),
             description: description.to_string(),
             argument_enum: None,
+            deprecated: false,
+            sensitive: false,
         };
-        let mut arguments = self.parameters.unwrap_or_default();
-        arguments.properties.insert(name.to_string(), argument);
-        arguments.required.push(name.to_string());
-        self.parameters = Some(arguments);
-
-        self
+        self.insert_parameter(name, argument, !T::OPTIONAL)
     }
     pub fn add_optional_parameter<T: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        let argument = ToolParameter {
+            schema: schema_of::<T>(),
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: false,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, false)
+    }
+    pub fn add_deprecated_parameter<T: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        let argument = ToolParameter {
+            schema: schema_of::<T>(),
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: true,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, false)
+    }
+    pub fn add_sensitive_parameter<T: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        let argument = ToolParameter {
+            schema: schema_of::<T>(),
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: false,
+            sensitive: true,
+        };
+        self.insert_parameter(name, argument, !T::OPTIONAL)
+    }
+    /// Adds an optional parameter advertising the value used when it is left
+    /// out. A default that does not serialize to JSON fails the build.
+    pub fn add_parameter_with_default<T: Jsonify + Serialize>(
         mut self,
         name: impl ToString,
         description: impl ToString,
+        default: T,
+    ) -> Self {
+        let name = name.to_string();
+        let mut schema = schema_of::<T>();
+        match serde_json::to_value(default) {
+            Ok(default) => {
+                schema.insert("default".to_string(), default);
+            }
+            Err(error) => {
+                self.invalid_default.get_or_insert((name.clone(), error));
+            }
+        }
+        let argument = ToolParameter {
+            schema,
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: false,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, false)
+    }
+    /// Adds a required parameter with a hand-built schema, e.g.
+    /// `Schema::any_of([Schema::of::<String>(), Schema::of::<f64>()])`.
+    pub fn add_schema_parameter(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+        schema: Schema,
     ) -> Self {
         let argument = ToolParameter {
-            argument_type: T::jsonify().as_str().unwrap().to_string(),
+            schema: schema_map(schema.into_value()),
             description: description.to_string(),
             argument_enum: None,
+            deprecated: false,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, true)
+    }
+    /// Adds a required parameter the model must always send as `value`,
+    /// e.g. a payload version.
+    pub fn add_const_parameter(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.add_schema_parameter(name, description, Schema::constant(value))
+    }
+    /// Merges the properties, `required` and `$defs` of an object schema
+    /// into the parameters.
+    fn splice_parameters(mut self, schema: serde_json::Value) -> Self {
+        let serde_json::Value::Object(mut schema) = schema else {
+            return self;
         };
+        let required = schema
+            .get("required")
+            .and_then(serde_json::Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if let Some(serde_json::Value::Object(defs)) = schema.remove("$defs") {
+            let mut arguments = self.parameters.unwrap_or_default();
+            arguments
+                .defs
+                .get_or_insert_with(Default::default)
+                .extend(defs);
+            self.parameters = Some(arguments);
+        }
+        if let Some(serde_json::Value::Object(properties)) = schema.remove("properties") {
+            for (name, property) in properties {
+                let mut property = schema_map(property);
+                let description = match property.remove("description") {
+                    Some(serde_json::Value::String(description)) => description,
+                    _ => String::new(),
+                };
+                let argument = ToolParameter {
+                    schema: property,
+                    description,
+                    argument_enum: None,
+                    deprecated: false,
+                    sensitive: false,
+                };
+                let required = required.contains(&name);
+                self = self.insert_parameter(name, argument, required);
+            }
+        }
+
+        self
+    }
+    /// Adds every property of an object schema, e.g. one built with
+    /// `SchemaBuilder`, as a parameter.
+    pub fn add_schema_parameters(self, schema: Schema) -> Self {
+        self.splice_parameters(schema.into_value())
+    }
+    /// Adds every field of `T`, typically a struct deriving `Object`, as a
+    /// parameter. The parameters can still be adjusted afterwards.
+    pub fn add_struct_parameters<T: Jsonify>(self) -> Self {
+        self.splice_parameters(T::jsonify())
+    }
+    /// Uses the fields of a `schemars::JsonSchema` struct as parameters.
+    #[cfg(feature = "schemars")]
+    pub fn add_schemars_parameters<T: schemars::JsonSchema>(self) -> Self {
+        self.splice_parameters(crate::schema::Schemars::<T>::jsonify())
+    }
+    /// Adds a parameter whose type is an object with its own properties,
+    /// typically a struct deriving `Object`.
+    pub fn add_object_parameter<T: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        self.add_parameter::<T>(name, description)
+    }
+    /// Changes an already added parameter.
+    fn update_parameter(mut self, name: &str, update: impl FnOnce(&mut ToolParameter)) -> Self {
+        match self
+            .parameters
+            .as_mut()
+            .and_then(|parameters| parameters.properties.get_mut(name))
+        {
+            Some(argument) => update(argument),
+            None => {
+                self.unknown_parameter
+                    .get_or_insert_with(|| name.to_string());
+            }
+        }
+
+        self
+    }
+    /// Adds a validation keyword to an already added parameter.
+    fn constrain(self, name: &str, keyword: &str, value: serde_json::Value) -> Self {
+        self.update_parameter(name, |argument| {
+            argument.schema.insert(keyword.to_string(), value);
+        })
+    }
+    pub fn title(self, name: &str, title: impl ToString) -> Self {
+        self.constrain(name, "title", json!(title.to_string()))
+    }
+    /// Marks an already added parameter as deprecated.
+    pub fn deprecate(self, name: &str) -> Self {
+        self.update_parameter(name, |argument| argument.deprecated = true)
+    }
+    pub fn minimum(self, name: &str, minimum: impl Into<serde_json::Value>) -> Self {
+        self.constrain(name, "minimum", minimum.into())
+    }
+    pub fn maximum(self, name: &str, maximum: impl Into<serde_json::Value>) -> Self {
+        self.constrain(name, "maximum", maximum.into())
+    }
+    pub fn exclusive_minimum(self, name: &str, minimum: impl Into<serde_json::Value>) -> Self {
+        self.constrain(name, "exclusiveMinimum", minimum.into())
+    }
+    pub fn exclusive_maximum(self, name: &str, maximum: impl Into<serde_json::Value>) -> Self {
+        self.constrain(name, "exclusiveMaximum", maximum.into())
+    }
+    pub fn pattern(self, name: &str, pattern: impl ToString) -> Self {
+        self.constrain(name, "pattern", json!(pattern.to_string()))
+    }
+    pub fn min_length(self, name: &str, length: u64) -> Self {
+        self.constrain(name, "minLength", json!(length))
+    }
+    pub fn max_length(self, name: &str, length: u64) -> Self {
+        self.constrain(name, "maxLength", json!(length))
+    }
+    pub fn format(self, name: &str, format: impl ToString) -> Self {
+        self.constrain(name, "format", json!(format.to_string()))
+    }
+    pub fn min_items(self, name: &str, items: u64) -> Self {
+        self.constrain(name, "minItems", json!(items))
+    }
+    pub fn max_items(self, name: &str, items: u64) -> Self {
+        self.constrain(name, "maxItems", json!(items))
+    }
+    pub fn unique_items(self, name: &str) -> Self {
+        self.constrain(name, "uniqueItems", json!(true))
+    }
+    pub fn examples(
+        self,
+        name: &str,
+        examples: impl IntoIterator<Item = impl Into<serde_json::Value>>,
+    ) -> Self {
+        let examples = examples.into_iter().map(Into::into).collect();
+        self.constrain(name, "examples", serde_json::Value::Array(examples))
+    }
+    pub fn deprecated(mut self) -> Self {
         let mut arguments = self.parameters.unwrap_or_default();
-        arguments.properties.insert(name.to_string(), argument);
+        arguments.deprecated = true;
         self.parameters = Some(arguments);
 
         self
     }
-    pub fn add_enum_parameter(
+    /// Sets OpenAI's `strict` flag. When enabled, also rejects unknown
+    /// arguments and requires every parameter, as strict mode expects, down
+    /// to the properties of nested objects. Applies to parameters added
+    /// before and after.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = Some(strict);
+        self
+    }
+    /// Requires exactly one of the given groups of already added parameters
+    /// to be present in full. Add the parameters as optional, otherwise they
+    /// are required regardless of the group.
+    pub fn one_of(
         mut self,
+        groups: impl IntoIterator<Item = impl IntoIterator<Item = impl ToString>>,
+    ) -> Self {
+        let mut arguments = self.parameters.unwrap_or_default();
+        let groups = groups
+            .into_iter()
+            .map(|group| {
+                let required = group
+                    .into_iter()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>();
+                if let Some(name) = required
+                    .iter()
+                    .find(|name| !arguments.properties.contains_key(*name))
+                {
+                    self.unknown_parameter.get_or_insert_with(|| name.clone());
+                }
+                json!({ "required": required })
+            })
+            .collect();
+        arguments.one_of = Some(groups);
+        self.parameters = Some(arguments);
+
+        self
+    }
+    pub fn add_enum_parameter(
+        self,
         name: impl ToString,
         description: impl ToString,
         enum_values: impl IntoIterator<Item = impl ToString>,
@@ -131,19 +822,16 @@ impl ToolBuilder {
             .map(|value| value.to_string())
             .collect();
         let argument = ToolParameter {
-            argument_type: "string".to_string(),
+            schema: schema_of::<String>(),
             description: description.to_string(),
             argument_enum: Some(variants),
+            deprecated: false,
+            sensitive: false,
         };
-        let mut arguments = self.parameters.unwrap_or_default();
-        arguments.properties.insert(name.to_string(), argument);
-        arguments.required.push(name.to_string());
-        self.parameters = Some(arguments);
-
-        self
+        self.insert_parameter(name, argument, true)
     }
     pub fn add_optional_enum_parameter(
-        mut self,
+        self,
         name: impl ToString,
         description: impl ToString,
         enum_values: impl IntoIterator<Item = impl ToString>,
@@ -153,76 +841,975 @@ impl ToolBuilder {
             .map(|value| value.to_string())
             .collect();
         let argument = ToolParameter {
-            argument_type: "string".to_string(),
+            schema: schema_of::<String>(),
             description: description.to_string(),
             argument_enum: Some(variants),
+            deprecated: false,
+            sensitive: false,
         };
-        let mut arguments = self.parameters.unwrap_or_default();
-        arguments.properties.insert(name.to_string(), argument);
-        self.parameters = Some(arguments);
-
-        self
+        self.insert_parameter(name, argument, false)
+    }
+    /// Adds a `Vec<T>` parameter, e.g.
+    /// `add_array_parameter::<String>("tags", "labels", |tags| tags.min_items(1).item("minLength", 1))`.
+    pub fn add_array_parameter<T: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+        constraints: impl FnOnce(ArrayParameter) -> ArrayParameter,
+    ) -> Self {
+        let ArrayParameter { keywords, items } = constraints(ArrayParameter::default());
+        let mut schema = schema_of::<Vec<T>>();
+        constrain_nested(&mut schema, "items", items);
+        schema.extend(keywords);
+        let argument = ToolParameter {
+            schema,
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: false,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, true)
+    }
+    /// Adds an object parameter with arbitrary keys and `V` values.
+    pub fn add_map_parameter<V: Jsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+        constraints: impl FnOnce(MapParameter) -> MapParameter,
+    ) -> Self {
+        let MapParameter { keywords, values } = constraints(MapParameter::default());
+        let mut schema = schema_of::<HashMap<String, V>>();
+        constrain_nested(&mut schema, "additionalProperties", values);
+        schema.extend(keywords);
+        let argument = ToolParameter {
+            schema,
+            description: description.to_string(),
+            argument_enum: None,
+            deprecated: false,
+            sensitive: false,
+        };
+        self.insert_parameter(name, argument, true)
+    }
+    /// Like [`Self::add_enum_parameter`] with the variants of `E`, which
+    /// keeps the schema in sync with the enum parsing the argument.
+    pub fn add_enum_parameter_of<E: EnumJsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        self.add_enum_parameter(name, description, E::VARIANTS)
+    }
+    pub fn add_optional_enum_parameter_of<E: EnumJsonify>(
+        self,
+        name: impl ToString,
+        description: impl ToString,
+    ) -> Self {
+        self.add_optional_enum_parameter(name, description, E::VARIANTS)
     }
+}
+
+impl ToolBuilder<Named, Described> {
     pub fn build(self) -> Result<Tool, ToolBuilderError> {
-        let name = self.name.ok_or(ToolBuilderError::NameNotSet)?;
-        let description = self
-            .description
-            .ok_or(ToolBuilderError::DescriptionNotSet)?;
-        let parameters = self.parameters.unwrap_or_default();
+        let name = self.name;
+        let description = self.description;
+        if let Some(name) = self.unknown_parameter {
+            return Err(ToolBuilderError::UnknownParameter(name));
+        }
+        if let Some((name, source)) = self.invalid_default {
+            return Err(ToolBuilderError::InvalidDefault { name, source });
+        }
+        if !valid_name(&name) {
+            return Err(ToolBuilderError::InvalidName(name));
+        }
+        let length = description.chars().count();
+        if length > MAX_DESCRIPTION_LENGTH {
+            return Err(ToolBuilderError::DescriptionTooLong(length));
+        }
+        let mut parameters = self.parameters.unwrap_or_default();
+        if self.strict == Some(true) {
+            parameters.additional_properties = Some(false);
+            let config = JsonifyConfig::default();
+            for (name, parameter) in parameters.properties.iter_mut() {
+                let mut schema = serde_json::Value::Object(std::mem::take(&mut parameter.schema));
+                strict_schema(&mut schema);
+                if !parameters.required.contains(name) {
+                    schema = config.or_null(schema);
+                }
+                parameter.schema = schema_map(schema);
+            }
+            parameters.required = parameters.properties.keys().cloned().collect();
+        }
+        let size = serde_json::to_vec(&parameters)
+            .map(|json| json.len())
+            .unwrap_or_default();
+        if size > MAX_SCHEMA_SIZE {
+            return Err(ToolBuilderError::SchemaTooLarge(size));
+        }
         let function = ToolFunction {
             name,
             description,
             parameters,
+            strict: self.strict,
         };
 
-        Ok(Tool {
-            tool_type: ToolType::Function,
-            function,
-        })
+        Ok(Tool::new(function))
     }
 }
 
-#[async_trait::async_trait]
-pub trait ToTool: fmt::Debug + Send + Sync {
+#[derive(Debug, Clone, Error)]
+pub enum ToolError {
+    #[error("Tool not found: {0}")]
+    NotFound(String),
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+    #[error("Tool failed: {0}")]
+    Failed(String),
+    #[error("Tool timed out after {0:?}")]
+    TimedOut(Duration),
+    #[error("Tool call was cancelled")]
+    Cancelled,
+    #[error("Tool {0} is currently disabled")]
+    Disabled(String),
+    #[error("Tool {0} is not allowed in this conversation")]
+    NotAllowed(String),
+    #[error("Tool {name} is rate limited, try again in {retry_after:?}")]
+    RateLimited { name: String, retry_after: Duration },
+}
+
+impl ToolError {
+    /// A short, stable name of the variant, e.g. for metrics labels.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::NotFound(_) => "not_found",
+            ToolError::InvalidArguments(_) => "invalid_arguments",
+            ToolError::Failed(_) => "failed",
+            ToolError::TimedOut(_) => "timed_out",
+            ToolError::Cancelled => "cancelled",
+            ToolError::Disabled(_) => "disabled",
+            ToolError::NotAllowed(_) => "not_allowed",
+            ToolError::RateLimited { .. } => "rate_limited",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait ToTool: fmt::Debug + Send + Sync {
     fn to_tool(&self) -> Tool;
-    async fn call_tool(&self, id: &str, input: serde_json::Value) -> ToolCallResult;
+    async fn call_tool(
+        &self,
+        id: &str,
+        input: serde_json::Value,
+    ) -> Result<ToolCallResult, ToolError>;
+    /// What [`Tools`] calls, with the context of
+    /// [`Tools::call_tools_with_context`]. Override to read the context;
+    /// results of calls with an unscoped, non-empty context are not cached.
+    async fn call_tool_with_context(
+        &self,
+        id: &str,
+        input: serde_json::Value,
+        _context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        self.call_tool(id, input).await
+    }
+    fn cacheable(&self) -> bool {
+        true
+    }
+    /// JSON Schema of the result content, e.g. `Output::jsonify()`.
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+/// A tool calling a closure, built with [`tool!`].
+pub struct FnTool<A, F> {
+    tool: Tool,
+    parse: fn(serde_json::Value) -> Result<A, ToolError>,
+    handler: F,
+}
+
+impl<A, F> FnTool<A, F> {
+    pub fn new<Fut, T, E>(
+        tool: Tool,
+        parse: fn(serde_json::Value) -> Result<A, ToolError>,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(A) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        Self {
+            tool,
+            parse,
+            handler,
+        }
+    }
+}
+
+impl<A, F> fmt::Debug for FnTool<A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnTool")
+            .field("name", &self.tool.function.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<A, F, Fut, T, E> ToTool for FnTool<A, F>
+where
+    A: Send + 'static,
+    F: Fn(A) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<T, E>> + Send,
+    T: Serialize,
+    E: fmt::Display,
+{
+    fn to_tool(&self) -> Tool {
+        self.tool.clone()
+    }
+    async fn call_tool(
+        &self,
+        id: &str,
+        input: serde_json::Value,
+    ) -> Result<ToolCallResult, ToolError> {
+        let args = (self.parse)(input)?;
+        let output = (self.handler)(args)
+            .await
+            .map_err(|e| ToolError::Failed(e.to_string()))?;
+        ToolCallResult::from_output(id, &output)
+    }
+}
+
+/// A tool with typed arguments and result. Every `TypedTool` is a
+/// [`ToTool`] whose parameters are the fields of `Input`, so the schema
+/// always matches what the arguments are parsed into. An invalid `NAME` or
+/// `DESCRIPTION` fails to compile.
+#[async_trait::async_trait]
+pub trait TypedTool: fmt::Debug + Send + Sync {
+    type Input: FromToolArgs + Jsonify + Send;
+    type Output: Serialize + Send;
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+    async fn run(&self, input: Self::Input) -> Result<Self::Output, ToolError>;
+    /// What the [`ToTool`] impl calls, with the context of
+    /// [`Tools::call_tools_with_context`]. Override to read the context.
+    async fn run_with_context(
+        &self,
+        input: Self::Input,
+        _context: &ToolContext,
+    ) -> Result<Self::Output, ToolError> {
+        self.run(input).await
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TypedTool> ToTool for T {
+    fn to_tool(&self) -> Tool {
+        const { assert_definition(T::NAME, T::DESCRIPTION) };
+        ToolBuilder::new()
+            .name(T::NAME)
+            .description(T::DESCRIPTION)
+            .add_struct_parameters::<T::Input>()
+            .build()
+            .expect("tool definition is complete")
+    }
+    async fn call_tool(
+        &self,
+        id: &str,
+        input: serde_json::Value,
+    ) -> Result<ToolCallResult, ToolError> {
+        self.call_tool_with_context(id, input, &ToolContext::new())
+            .await
+    }
+    async fn call_tool_with_context(
+        &self,
+        id: &str,
+        input: serde_json::Value,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let input = T::Input::from_args(input)?;
+        let output = self.run_with_context(input, context).await?;
+        ToolCallResult::from_output(id, &output)
+    }
+}
+
+/// Builds a [`FnTool`] without declaring an argument struct. Parameters
+/// marked with `?` are optional and arrive as `Option`s. `name` and
+/// `description` are checked at compile time, so they must be constants.
+///
+/// ```ignore
+/// let weather = tool! {
+///     name: "get_weather",
+///     description: "current weather in a city",
+///     params: { city: String ["city name"], unit?: Unit },
+///     |args| async move { forecast(&args.city, args.unit).await },
+/// };
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tool {
+    (
+        name: $name:expr,
+        description: $description:expr,
+        params: { $($params:tt)* },
+        $handler:expr $(,)?
+    ) => {
+        $crate::__tool!(@params [] [$name, $description, $handler] $($params)*)
+    };
+    (@params [$($done:tt)*] $tool:tt
+        $param:ident ? : $ty:ty $([$param_description:expr])? $(, $($rest:tt)*)?
+    ) => {
+        $crate::__tool!(@params
+            [$($done)* ($param, ::core::option::Option<$ty>, [$($param_description,)? ""])]
+            $tool $($($rest)*)?
+        )
+    };
+    (@params [$($done:tt)*] $tool:tt
+        $param:ident : $ty:ty $([$param_description:expr])? $(, $($rest:tt)*)?
+    ) => {
+        $crate::__tool!(@params
+            [$($done)* ($param, $ty, [$($param_description,)? ""])]
+            $tool $($($rest)*)?
+        )
+    };
+    (@params [$(($param:ident, $ty:ty, $param_description:expr))*]
+        [$name:expr, $description:expr, $handler:expr]
+    ) => {{
+        struct Args {
+            $($param: $ty,)*
+        }
+        fn parse(
+            input: $crate::__private::serde_json::Value,
+        ) -> ::core::result::Result<Args, $crate::tools::ToolError> {
+            #[allow(unused_mut, unused_variables)]
+            let mut input = $crate::args::object(input)?;
+            Ok(Args {
+                $($param: $crate::args::field(&mut input, stringify!($param))?,)*
+            })
+        }
+        const _: () = $crate::tools::assert_definition($name, $description);
+        let tool = $crate::tools::ToolBuilder::new()
+            .name($name)
+            .description($description)
+            $(.add_parameter::<$ty>(stringify!($param), $param_description[0]))*
+            .build()
+            .expect("tool definition is complete");
+        $crate::tools::FnTool::new(tool, parse, $handler)
+    }};
+}
+
+pub use crate::__tool as tool;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolDescription {
+    pub name: String,
+    pub tool: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Error)]
+pub enum OutputValidationError {
+    #[error("Tool not found: {0}")]
+    ToolNotFound(String),
+    #[error("Output is not valid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("Output does not match schema: {0:?}")]
+    SchemaMismatch(Vec<SchemaViolation>),
+}
+
+pub const REDACTED: &str = "***";
+
+/// Joins the namespace and tool name in [`Tools::namespaced`].
+pub const NAMESPACE_SEPARATOR: &str = "__";
+
+/// What [`Tools::merge`] and [`Tools::extend`] do with a tool whose name is
+/// already registered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    #[default]
+    Error,
+    KeepFirst,
+    Overwrite,
+}
+
+#[derive(Debug, Error)]
+pub enum MergeError {
+    /// Nothing was added, `tools` is the registry as it was before.
+    #[error("Tool {name} is already registered")]
+    Conflict { name: String, tools: Box<Tools> },
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct Tools(pub HashMap<String, (serde_json::Value, Arc<dyn ToTool>)>);
+pub struct Tools {
+    /// In registration order, which is also the serialization order.
+    tools: IndexMap<String, (serde_json::Value, Arc<dyn ToTool>)>,
+    middleware: Vec<Arc<dyn ToolMiddleware>>,
+    cache: Option<Arc<dyn ToolCache>>,
+    concurrency: Option<Arc<Semaphore>>,
+    metrics: Option<Arc<dyn ToolMetrics>>,
+    audit: Option<Arc<dyn AuditSink>>,
+    /// Shared by clones, so that a tool can be turned off everywhere.
+    disabled: Arc<RwLock<HashSet<String>>>,
+    timeout: Option<Duration>,
+    tool_timeouts: HashMap<String, Duration>,
+}
 
 impl Tools {
     pub fn add_tool<T>(mut self, toolable: T) -> Self
+    where
+        T: ToTool + 'static,
+    {
+        self.replace_tool(toolable);
+        self
+    }
+    /// Registers `toolable`, returning the tool previously registered under
+    /// the same name.
+    pub fn replace_tool<T>(&mut self, toolable: T) -> Option<Arc<dyn ToTool>>
     where
         T: ToTool + 'static,
     {
         let tool = toolable.to_tool();
-        let json = serde_json::to_value(&tool).unwrap();
-        let name = tool.function.name.clone();
-        self.0.insert(name, (json, Arc::new(toolable)));
+        let json = tool.to_value().expect("tools serialize to JSON").clone();
+        self.tools
+            .insert(tool.function.name, (json, Arc::new(toolable)))
+            .map(|(_, tool)| tool)
+    }
+    /// Adds the tools of `other`, which stay disabled if they were and keep
+    /// their timeouts. Its middleware, cache and other settings are dropped.
+    pub fn merge(self, other: Tools, policy: ConflictPolicy) -> Result<Self, MergeError> {
+        let disabled = other.disabled.read().unwrap().clone();
+        let added = other
+            .tools
+            .keys()
+            .filter(|name| policy != ConflictPolicy::KeepFirst || !self.contains(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut merged = self.insert_all(other.tools, policy)?;
+        for name in added {
+            merged.set_enabled(&name, !disabled.contains(&name));
+            match other.tool_timeouts.get(&name) {
+                Some(timeout) => merged.tool_timeouts.insert(name, *timeout),
+                None => merged.tool_timeouts.remove(&name),
+            };
+        }
+        Ok(merged)
+    }
+    pub fn extend<I>(self, tools: I, policy: ConflictPolicy) -> Result<Self, MergeError>
+    where
+        I: IntoIterator<Item = Arc<dyn ToTool>>,
+    {
+        let entries = tools.into_iter().map(|tool| {
+            let definition = tool.to_tool();
+            let json = definition
+                .to_value()
+                .expect("tools serialize to JSON")
+                .clone();
+            (definition.function.name, (json, tool))
+        });
+        self.insert_all(entries, policy)
+    }
+    fn insert_all(
+        mut self,
+        entries: impl IntoIterator<Item = (String, (serde_json::Value, Arc<dyn ToTool>))>,
+        policy: ConflictPolicy,
+    ) -> Result<Self, MergeError> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        if policy == ConflictPolicy::Error {
+            if let Some((name, _)) = entries.iter().find(|(name, _)| self.contains(name)) {
+                return Err(MergeError::Conflict {
+                    name: name.clone(),
+                    tools: Box::new(self),
+                });
+            }
+        }
+        for (name, entry) in entries {
+            if policy == ConflictPolicy::KeepFirst && self.contains(&name) {
+                continue;
+            }
+            self.tools.insert(name, entry);
+        }
+        Ok(self)
+    }
+    /// Exposes every tool as `{namespace}__{name}`, e.g. `fs__read_file`.
+    /// Calls must use the prefixed name, tools themselves are unaffected.
+    /// Fails if the namespace or a prefixed name is not a valid tool name.
+    pub fn namespaced(mut self, namespace: &str) -> Result<Self, ToolBuilderError> {
+        let prefixed = |name: String| format!("{namespace}{NAMESPACE_SEPARATOR}{name}");
+        if !valid_name(namespace) {
+            return Err(ToolBuilderError::InvalidName(namespace.to_string()));
+        }
+        if let Some(name) = self
+            .tools
+            .keys()
+            .map(|name| prefixed(name.clone()))
+            .find(|name| !valid_name(name))
+        {
+            return Err(ToolBuilderError::InvalidName(name));
+        }
+        self.tools = self
+            .tools
+            .into_iter()
+            .map(|(name, (mut json, tool))| {
+                let name = prefixed(name);
+                json["function"]["name"] = json!(name);
+                (name, (json, tool))
+            })
+            .collect();
+        self.tool_timeouts = self
+            .tool_timeouts
+            .into_iter()
+            .map(|(name, timeout)| (prefixed(name), timeout))
+            .collect();
+        // Not shared with clones anymore, as their names differ.
+        let disabled = self.disabled.read().unwrap().clone();
+        let disabled = disabled.into_iter().map(prefixed).collect();
+        self.disabled = Arc::new(RwLock::new(disabled));
+        Ok(self)
+    }
+    /// A view of the named tools. Unknown names are ignored.
+    pub fn subset(&self, names: impl IntoIterator<Item = impl ToString>) -> ToolsView<'_> {
+        let names = names
+            .into_iter()
+            .map(|name| name.to_string())
+            .filter(|name| self.contains(name))
+            .collect();
+        ToolsView { tools: self, names }
+    }
+    /// A view of the tools for which `predicate` returns true.
+    pub fn filter(&self, predicate: impl Fn(&str, &dyn ToTool) -> bool) -> ToolsView<'_> {
+        let names = self
+            .tools
+            .iter()
+            .filter(|(name, (_, tool))| predicate(name, tool.as_ref()))
+            .map(|(name, _)| name.clone())
+            .collect();
+        ToolsView { tools: self, names }
+    }
+    /// Disabled tools are left out of the serialized registry and calls to
+    /// them fail with [`ToolError::Disabled`]. Affects all clones.
+    pub fn set_enabled(&self, name: &str, enabled: bool) {
+        let mut disabled = self.disabled.write().unwrap();
+        if enabled {
+            disabled.remove(name);
+        } else {
+            disabled.insert(name.to_string());
+        }
+    }
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.read().unwrap().contains(name)
+    }
+    pub fn remove_tool(&mut self, name: &str) -> Option<Arc<dyn ToTool>> {
+        let (_, tool) = self.tools.shift_remove(name)?;
+        self.disabled.write().unwrap().remove(name);
+        Some(tool)
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ToTool>> {
+        self.tools.get(name).map(|(_, tool)| tool)
+    }
+    /// Names of the registered tools, enabled or not, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().map(String::as_str)
+    }
+    /// The registered tools, enabled or not, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<dyn ToTool>)> {
+        self.tools
+            .iter()
+            .map(|(name, (_, tool))| (name.as_str(), tool))
+    }
+    /// The JSON of every registered tool, as it is serialized.
+    pub(crate) fn definitions(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.tools
+            .iter()
+            .map(|(name, (json, _))| (name.as_str(), json))
+    }
+    /// Middleware added first is the outermost layer.
+    pub fn with_middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: ToolMiddleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+    /// Adds middleware written as a closure, see [`from_fn`].
+    pub fn layer<F>(self, f: F) -> Self
+    where
+        F: for<'a> Fn(
+                &'a ToolCall,
+                Next<'a>,
+            )
+                -> futures::future::BoxFuture<'a, Result<ToolCallResult, ToolError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.with_middleware(from_fn(f))
+    }
+    /// Reports the duration and outcome of every call to `metrics`.
+    pub fn with_metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: ToolMetrics + 'static,
+    {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+    /// Reports every call to `sink`, arguments redacted as in
+    /// [`Tools::redact_arguments`]. Add an [`AuditContext`] to the
+    /// [`ToolContext`] to say who the calls run for.
+    pub fn with_audit<S>(mut self, sink: S) -> Self
+    where
+        S: AuditSink + 'static,
+    {
+        self.audit = Some(Arc::new(sink));
+        self
+    }
+    pub fn with_cache<C>(mut self, cache: C) -> Self
+    where
+        C: ToolCache + 'static,
+    {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+    /// Runs at most `limit` calls at once, the rest wait for a free slot.
+    ///
+    /// # Panics
+    ///
+    /// If `limit` is zero, which would leave every call waiting forever.
+    pub fn with_max_concurrency(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "max concurrency must be positive");
+        self.concurrency = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+    /// Fails calls that take longer than `timeout`. Needs a Tokio runtime.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Overrides [`Tools::with_timeout`] for the tool `name`.
+    pub fn with_tool_timeout(mut self, name: impl ToString, timeout: Duration) -> Self {
+        self.tool_timeouts.insert(name.to_string(), timeout);
         self
     }
-    async fn call_tool(&self, tool_call: &ToolCall) -> ToolCallResult {
+    async fn call_tool(&self, tool_call: &ToolCall, options: &CallOptions<'_>) -> ToolCallResult {
+        match self.try_call_one_with(tool_call, options).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::text(&tool_call.id, e),
+        }
+    }
+    /// Like [`Tools::call_one`], but returns unknown tools, malformed
+    /// arguments and handler failures as errors instead of error results.
+    pub async fn try_call_one(&self, tool_call: &ToolCall) -> Result<ToolCallResult, ToolError> {
+        self.try_call_one_with(tool_call, &CallOptions::default())
+            .await
+    }
+    pub async fn try_call_one_with_context(
+        &self,
+        tool_call: &ToolCall,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
+        let options = CallOptions {
+            context,
+            ..Default::default()
+        };
+        self.try_call_one_with(tool_call, &options).await
+    }
+    /// Like [`Tools::try_call_one`], with the settings of `options`. Calls
+    /// it stops are recorded in metrics and audit like any other.
+    pub async fn try_call_one_with(
+        &self,
+        tool_call: &ToolCall,
+        options: &CallOptions<'_>,
+    ) -> Result<ToolCallResult, ToolError> {
+        let timestamp = SystemTime::now();
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        let result = self.traced_call(tool_call, options).await;
+        #[cfg(not(feature = "tracing"))]
+        let result = self.run_call(tool_call, options).await;
+        if let Some(metrics) = &self.metrics {
+            let name = match &result {
+                Err(ToolError::NotFound(_)) => crate::metrics::UNKNOWN_TOOL,
+                _ => &tool_call.function.name,
+            };
+            metrics.record(name, start.elapsed(), result.as_ref().err());
+        }
+        if let Some(audit) = &self.audit {
+            audit
+                .record(self.audit_record(
+                    tool_call,
+                    options.context,
+                    &result,
+                    timestamp,
+                    start.elapsed(),
+                ))
+                .await;
+        }
+        result
+    }
+    fn audit_record(
+        &self,
+        tool_call: &ToolCall,
+        context: &ToolContext,
+        result: &Result<ToolCallResult, ToolError>,
+        timestamp: SystemTime,
+        elapsed: Duration,
+    ) -> AuditRecord {
+        let ToolCallFunction { name, arguments } = &tool_call.function;
+        let arguments = match serde_json::from_str(arguments) {
+            Ok(arguments) => self.redact_arguments(name, &arguments),
+            Err(_) => json!(REDACTED),
+        };
+        let timestamp_ms = timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        AuditRecord {
+            timestamp_ms: timestamp_ms as u64,
+            call_id: tool_call.id.clone(),
+            tool: name.clone(),
+            arguments,
+            result: result.as_ref().ok().map(ToolCallResult::to_text),
+            error: result.as_ref().err().map(ToString::to_string),
+            duration_ms: elapsed.as_millis() as u64,
+            context: context
+                .get::<AuditContext>()
+                .map(|context| context.0.clone()),
+        }
+    }
+    /// Runs the call in a `tool_call` span, recording its duration and
+    /// outcome once it finishes.
+    #[cfg(feature = "tracing")]
+    async fn traced_call(
+        &self,
+        tool_call: &ToolCall,
+        options: &CallOptions<'_>,
+    ) -> Result<ToolCallResult, ToolError> {
+        use tracing::{field::Empty, Instrument};
+
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %tool_call.function.name,
+            id = %tool_call.id,
+            argument_bytes = tool_call.function.arguments.len(),
+            duration_ms = Empty,
+            outcome = Empty,
+        );
+        let start = Instant::now();
+        let result = self
+            .run_call(tool_call, options)
+            .instrument(span.clone())
+            .await;
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        span.record(
+            "outcome",
+            result.as_ref().map_or_else(ToolError::kind, |_| "ok"),
+        );
+        tracing::debug!(parent: &span, "tool call finished");
+        result
+    }
+    async fn run_call(
+        &self,
+        tool_call: &ToolCall,
+        options: &CallOptions<'_>,
+    ) -> Result<ToolCallResult, ToolError> {
+        let name = &tool_call.function.name;
+        if options.policy.is_some_and(|policy| !policy.allows(name)) {
+            return Err(ToolError::NotAllowed(name.clone()));
+        }
+        let call = async {
+            let _permit = match &self.concurrency {
+                Some(semaphore) => semaphore.acquire().await.ok(),
+                None => None,
+            };
+            Next::new(self, &self.middleware, options.context)
+                .run(tool_call)
+                .await
+        };
+        match options.cancel {
+            Some(cancel) => cancel
+                .run_until_cancelled(call)
+                .await
+                .unwrap_or(Err(ToolError::Cancelled)),
+            None => call.await,
+        }
+    }
+    pub(crate) async fn dispatch(
+        &self,
+        tool_call: &ToolCall,
+        context: &ToolContext,
+    ) -> Result<ToolCallResult, ToolError> {
         let function_name = &tool_call.function.name;
         let id = &tool_call.id;
-        if let Some((_, tool)) = self.0.get(function_name) {
-            let json = serde_json::from_str(&tool_call.function.arguments).unwrap();
-            tool.call_tool(id, json).await
-        } else {
-            ToolCallResult {
-                tool_call_id: id.clone(),
-                content: json!("Tool not found").to_string(),
+        let (_, tool) = self
+            .tools
+            .get(function_name)
+            .ok_or_else(|| ToolError::NotFound(function_name.clone()))?;
+        if !self.is_enabled(function_name) {
+            return Err(ToolError::Disabled(function_name.clone()));
+        }
+        let json = serde_json::from_str(&tool_call.function.arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+        // Unscoped context values may change the result, so such calls
+        // bypass the cache rather than share one caller's answer.
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|_| tool.cacheable() && (context.is_empty() || context.scope().is_some()));
+        let arguments = match (cache, context.scope()) {
+            (None, _) => String::new(),
+            (Some(_), None) => normalize_arguments(&json),
+            (Some(_), Some(scope)) => normalize_arguments(&json!([scope, json])),
+        };
+        if let Some(cache) = cache {
+            if let Some(result) = cache.get(function_name, &arguments).await {
+                return Ok(ToolCallResult {
+                    tool_call_id: id.clone(),
+                    ..result
+                });
             }
         }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            tool = %function_name,
+            id = %id,
+            arguments = %self.redact_arguments(function_name, &json),
+            "calling tool"
+        );
+        let call = tool.call_tool_with_context(id, json, context);
+        let timeout = self
+            .tool_timeouts
+            .get(function_name)
+            .or(self.timeout.as_ref());
+        let result = match timeout {
+            Some(&timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| ToolError::TimedOut(timeout))??,
+            None => call.await?,
+        };
+        if let Some(cache) = cache {
+            cache.set(function_name, &arguments, result.clone()).await;
+        }
+        Ok(result)
+    }
+    #[must_use]
+    pub async fn call_one(&self, tool_call: &ToolCall) -> ToolCallResult {
+        self.call_tool(tool_call, &CallOptions::default()).await
     }
+    /// Runs the calls concurrently, returning the results in call order.
     #[must_use]
     pub async fn call_tools(&self, tool_calls: &[ToolCall]) -> ToolsResults {
-        let mut results = ToolsResults::new();
+        self.call_tools_with(tool_calls, CallOptions::default())
+            .await
+    }
+    /// Like [`Tools::call_tools`], handing `context` to every tool and
+    /// middleware.
+    #[must_use]
+    pub async fn call_tools_with_context(
+        &self,
+        tool_calls: &[ToolCall],
+        context: &ToolContext,
+    ) -> ToolsResults {
+        let options = CallOptions {
+            context,
+            ..Default::default()
+        };
+        self.call_tools_with(tool_calls, options).await
+    }
+    /// Like [`Tools::call_tools`], with the settings of `options`.
+    #[must_use]
+    pub async fn call_tools_with(
+        &self,
+        tool_calls: &[ToolCall],
+        options: CallOptions<'_>,
+    ) -> ToolsResults {
+        if !options.dedup {
+            let calls = tool_calls
+                .iter()
+                .map(|tool_call| self.call_tool(tool_call, &options));
+            return ToolsResults(join_all(calls).await);
+        }
+        fn key(tool_call: &ToolCall) -> (&str, &str) {
+            (&tool_call.function.name, &tool_call.function.arguments)
+        }
+        let mut unique = IndexMap::new();
         for tool_call in tool_calls {
-            let result = self.call_tool(tool_call).await;
-            results.add_result(result);
+            unique.entry(key(tool_call)).or_insert(tool_call);
+        }
+        let calls = unique
+            .values()
+            .map(|tool_call| self.call_tool(tool_call, &options));
+        let results = join_all(calls).await;
+        let seen = unique.keys().zip(results).collect::<HashMap<_, _>>();
+        let results = tool_calls
+            .iter()
+            .map(|tool_call| ToolCallResult {
+                tool_call_id: tool_call.id.clone(),
+                ..seen[&key(tool_call)].clone()
+            })
+            .collect();
+        ToolsResults(results)
+    }
+    /// The enabled tools in registration order, like the serialized registry.
+    pub fn describe(&self) -> Vec<ToolDescription> {
+        self.tools
+            .iter()
+            .filter(|(name, _)| self.is_enabled(name))
+            .map(|(name, (json, tool))| ToolDescription {
+                name: name.clone(),
+                tool: json.clone(),
+                output_schema: tool.output_schema(),
+            })
+            .collect()
+    }
+    /// Checks the result content against the tool's declared output schema.
+    /// Tools without an output schema always pass.
+    pub fn validate_output(
+        &self,
+        name: &str,
+        result: &ToolCallResult,
+    ) -> Result<(), OutputValidationError> {
+        let (_, tool) = self
+            .tools
+            .get(name)
+            .ok_or_else(|| OutputValidationError::ToolNotFound(name.to_string()))?;
+        let Some(schema) = tool.output_schema() else {
+            return Ok(());
+        };
+        let output = serde_json::from_str(&result.to_text())?;
+        let violations = validate(&schema, &output);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(OutputValidationError::SchemaMismatch(violations))
+        }
+    }
+    /// Masks the values of arguments whose property is marked `x-sensitive`
+    /// in the tool's schema.
+    pub fn redact_arguments(&self, name: &str, arguments: &serde_json::Value) -> serde_json::Value {
+        let mut arguments = arguments.clone();
+        let properties = self
+            .tools
+            .get(name)
+            .map(|(json, _)| &json["function"]["parameters"]["properties"]);
+        if let (Some(properties), Some(values)) = (properties, arguments.as_object_mut()) {
+            for (key, value) in values.iter_mut() {
+                if properties[key.as_str()]["x-sensitive"] == json!(true) {
+                    *value = json!(REDACTED);
+                }
+            }
         }
-        results
+        arguments
+    }
+    /// Like [`Tools::call_tools`], but calls with identical name and arguments
+    /// run the tool once and share its result.
+    #[must_use]
+    pub async fn call_tools_dedup(&self, tool_calls: &[ToolCall]) -> ToolsResults {
+        let options = CallOptions {
+            dedup: true,
+            ..Default::default()
+        };
+        self.call_tools_with(tool_calls, options).await
     }
 }
 
@@ -231,9 +1818,122 @@ impl serde::Serialize for Tools {
     where
         S: serde::Serializer,
     {
-        self.0
-            .values()
-            .map(|(json, _)| json)
+        self.tools
+            .iter()
+            .filter(|(name, _)| self.is_enabled(name))
+            .map(|(_, (json, _))| json)
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+/// Settings for one run of [`Tools::call_tools_with`], e.g.
+/// `CallOptions { context: &context, cancel: Some(&token), ..Default::default() }`.
+#[derive(Debug, Clone, Copy)]
+pub struct CallOptions<'a> {
+    /// Handed to every tool and middleware.
+    pub context: &'a ToolContext,
+    /// Calls to tools it does not allow fail with [`ToolError::NotAllowed`]
+    /// without running.
+    pub policy: Option<&'a CallPolicy>,
+    /// Once cancelled, unfinished calls are dropped and answered with
+    /// [`ToolError::Cancelled`].
+    pub cancel: Option<&'a CancellationToken>,
+    /// Calls with identical name and arguments run the tool once and share
+    /// its result.
+    pub dedup: bool,
+}
+
+impl Default for CallOptions<'_> {
+    fn default() -> Self {
+        static EMPTY: LazyLock<ToolContext> = LazyLock::new(ToolContext::new);
+        Self {
+            context: &EMPTY,
+            policy: None,
+            cancel: None,
+            dedup: false,
+        }
+    }
+}
+
+/// Which tools may run for one call of [`Tools::call_tools_with`], see
+/// [`CallOptions::policy`].
+/// Allows every tool by default; a denied name wins over an allowed one.
+#[derive(Debug, Clone, Default)]
+pub struct CallPolicy {
+    allow: Option<HashSet<String>>,
+    deny: HashSet<String>,
+}
+
+impl CallPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Allows only the given tools, and those of earlier `allow` calls.
+    pub fn allow(mut self, names: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.allow
+            .get_or_insert_with(HashSet::new)
+            .extend(names.into_iter().map(|name| name.to_string()));
+        self
+    }
+    pub fn deny(mut self, names: impl IntoIterator<Item = impl ToString>) -> Self {
+        self.deny
+            .extend(names.into_iter().map(|name| name.to_string()));
+        self
+    }
+    pub fn allows(&self, name: &str) -> bool {
+        !self.deny.contains(name) && self.allow.as_ref().is_none_or(|allow| allow.contains(name))
+    }
+}
+
+/// The part of a [`Tools`] registry selected with [`Tools::subset`] or
+/// [`Tools::filter`]. Serializes and calls only the selected tools, going
+/// through the registry's middleware and settings.
+#[derive(Debug, Clone)]
+pub struct ToolsView<'a> {
+    tools: &'a Tools,
+    names: HashSet<String>,
+}
+
+impl ToolsView<'_> {
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+    pub async fn try_call_one(&self, tool_call: &ToolCall) -> Result<ToolCallResult, ToolError> {
+        if !self.contains(&tool_call.function.name) {
+            return Err(ToolError::NotFound(tool_call.function.name.clone()));
+        }
+        self.tools.try_call_one(tool_call).await
+    }
+    #[must_use]
+    pub async fn call_one(&self, tool_call: &ToolCall) -> ToolCallResult {
+        match self.try_call_one(tool_call).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::text(&tool_call.id, e),
+        }
+    }
+    #[must_use]
+    pub async fn call_tools(&self, tool_calls: &[ToolCall]) -> ToolsResults {
+        let calls = tool_calls.iter().map(|tool_call| self.call_one(tool_call));
+        ToolsResults(join_all(calls).await)
+    }
+    pub fn describe(&self) -> Vec<ToolDescription> {
+        let mut descriptions = self.tools.describe();
+        descriptions.retain(|description| self.contains(&description.name));
+        descriptions
+    }
+}
+
+impl serde::Serialize for ToolsView<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.tools
+            .tools
+            .iter()
+            .filter(|(name, _)| self.contains(name) && self.tools.is_enabled(name))
+            .map(|(_, (json, _))| json)
             .collect::<Vec<_>>()
             .serialize(serializer)
     }
@@ -242,7 +1942,93 @@ impl serde::Serialize for Tools {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCallResult {
     pub tool_call_id: String,
-    pub content: String,
+    pub content: Vec<ToolResultContent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ToolResultContent {
+    Text { text: String },
+    Image { media_type: String, data: String },
+}
+
+impl ToolCallResult {
+    pub fn new(tool_call_id: impl ToString) -> Self {
+        Self {
+            tool_call_id: tool_call_id.to_string(),
+            content: Vec::new(),
+        }
+    }
+    pub fn text(tool_call_id: impl ToString, text: impl ToString) -> Self {
+        let mut result = Self::new(tool_call_id);
+        result.push_text(text);
+        result
+    }
+    /// `output` as a text block, strings verbatim and anything else as JSON.
+    pub fn from_output(
+        tool_call_id: impl ToString,
+        output: &impl Serialize,
+    ) -> Result<Self, ToolError> {
+        let text =
+            match serde_json::to_value(output).map_err(|e| ToolError::Failed(e.to_string()))? {
+                serde_json::Value::String(text) => text,
+                value => value.to_string(),
+            };
+        Ok(Self::text(tool_call_id, text))
+    }
+    pub fn push_text(&mut self, text: impl ToString) -> &mut Self {
+        self.content.push(ToolResultContent::Text {
+            text: text.to_string(),
+        });
+        self
+    }
+    /// `data` is the base64 encoded image.
+    pub fn push_image(&mut self, media_type: impl ToString, data: impl ToString) -> &mut Self {
+        self.content.push(ToolResultContent::Image {
+            media_type: media_type.to_string(),
+            data: data.to_string(),
+        });
+        self
+    }
+    /// All blocks collapsed into a single string, images replaced by a
+    /// placeholder.
+    pub fn to_text(&self) -> String {
+        self.content
+            .iter()
+            .map(|block| match block {
+                ToolResultContent::Text { text } => text.clone(),
+                ToolResultContent::Image { media_type, .. } => format!("[image: {media_type}]"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// An OpenAI `tool` message. OpenAI only accepts string content.
+    pub fn to_openai_value(&self) -> serde_json::Value {
+        json!({
+            "role": "tool",
+            "tool_call_id": self.tool_call_id,
+            "content": self.to_text(),
+        })
+    }
+    /// An Anthropic `tool_result` content block.
+    pub fn to_anthropic_value(&self) -> serde_json::Value {
+        let content = self
+            .content
+            .iter()
+            .map(|block| match block {
+                ToolResultContent::Text { text } => json!({ "type": "text", "text": text }),
+                ToolResultContent::Image { media_type, data } => json!({
+                    "type": "image",
+                    "source": { "type": "base64", "media_type": media_type, "data": data },
+                }),
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "type": "tool_result",
+            "tool_use_id": self.tool_call_id,
+            "content": content,
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -256,3 +2042,2084 @@ impl ToolsResults {
         self.0.push(result);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::cache::MemoryCache;
+
+    #[derive(Debug, Default)]
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl ToTool for Counter {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("counter")
+                .description("counts calls")
+                .add_parameter::<i32>("step", "step size")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            _input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            let count = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(ToolCallResult::text(id, count))
+        }
+    }
+
+    fn tool_call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            tool_type: ToolType::Function,
+            function: ToolCallFunction {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Uncached(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl ToTool for Uncached {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("uncached")
+                .description("counts calls")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            Counter(self.0.clone()).call_tool(id, input).await
+        }
+        fn cacheable(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let cached = Arc::new(AtomicUsize::new(0));
+        let uncached = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Counter(cached.clone()))
+            .add_tool(Uncached(uncached.clone()))
+            .with_cache(MemoryCache::new());
+
+        for (id, arguments) in [("call_1", r#"{"step":1}"#), ("call_2", r#"{ "step": 1 }"#)] {
+            let results = tools
+                .call_tools(&[
+                    tool_call(id, "counter", arguments),
+                    tool_call(id, "uncached", "{}"),
+                ])
+                .await;
+            assert_eq!(results.0[0].tool_call_id, id);
+            assert_eq!(results.0[0].to_text(), "1");
+        }
+        assert_eq!(cached.load(Ordering::SeqCst), 1);
+        assert_eq!(uncached.load(Ordering::SeqCst), 2);
+    }
+
+    #[derive(Debug)]
+    struct Weather;
+
+    #[async_trait::async_trait]
+    impl ToTool for Weather {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("weather")
+                .description("current weather")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            _input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            Ok(ToolCallResult::text(
+                id,
+                json!({"temperature": 21.5, "unit": "celsius"}),
+            ))
+        }
+        fn output_schema(&self) -> Option<serde_json::Value> {
+            #[allow(dead_code)]
+            #[derive(ai_tools_ox_derive::Object)]
+            struct Output {
+                temperature: f64,
+                unit: String,
+            }
+            Some(Output::jsonify())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_schema() {
+        let tools = Tools::default().add_tool(Weather).add_tool(Login);
+
+        let descriptions = tools.describe();
+        assert_eq!(descriptions[0].name, "weather");
+        assert_eq!(
+            descriptions[0].output_schema.as_ref().unwrap()["properties"]["unit"],
+            json!({"type": "string"})
+        );
+        assert!(descriptions[1].output_schema.is_none());
+
+        let result = tools.call_one(&tool_call("call_1", "weather", "{}")).await;
+        assert!(tools.validate_output("weather", &result).is_ok());
+
+        let result = ToolCallResult::text("call_1", json!({"temperature": "warm"}));
+        assert!(matches!(
+            tools.validate_output("weather", &result),
+            Err(OutputValidationError::SchemaMismatch(_))
+        ));
+    }
+
+    #[derive(Debug)]
+    struct Login;
+
+    #[async_trait::async_trait]
+    impl ToTool for Login {
+        fn to_tool(&self) -> Tool {
+            ToolBuilder::new()
+                .name("login")
+                .description("logs in")
+                .add_parameter::<String>("user", "user name")
+                .add_sensitive_parameter::<String>("password", "user password")
+                .build()
+                .unwrap()
+        }
+        async fn call_tool(
+            &self,
+            id: &str,
+            _input: serde_json::Value,
+        ) -> Result<ToolCallResult, ToolError> {
+            Ok(ToolCallResult::text(id, "ok"))
+        }
+    }
+
+    #[test]
+    fn test_redact_arguments() {
+        let tools = Tools::default().add_tool(Login);
+        let redacted =
+            tools.redact_arguments("login", &json!({"user": "alice", "password": "hunter2"}));
+        assert_eq!(redacted, json!({"user": "alice", "password": REDACTED}));
+    }
+
+    /// Collects what is logged on this thread until the guard is dropped.
+    #[cfg(feature = "tracing")]
+    fn capture_logs() -> (LogBuffer, tracing::subscriber::DefaultGuard) {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        (buffer, tracing::subscriber::set_default(subscriber))
+    }
+
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "tracing")]
+    impl LogBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_redacts_sensitive_arguments() {
+        let (buffer, _guard) = capture_logs();
+
+        let tools = Tools::default().add_tool(Login);
+        let _ = tools
+            .call_tools(&[tool_call(
+                "call_1",
+                "login",
+                r#"{"user":"alice","password":"hunter2"}"#,
+            )])
+            .await;
+
+        let log = buffer.contents();
+        assert!(log.contains("alice"));
+        assert!(log.contains(REDACTED));
+        assert!(!log.contains("hunter2"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_spans() {
+        let (buffer, _guard) = capture_logs();
+
+        let tools = Tools::default().add_tool(AddTool);
+        let _ = tools
+            .call_tools(&[
+                tool_call("call_1", "add", r#"{"a":1}"#),
+                tool_call("call_2", "sub", "{}"),
+            ])
+            .await;
+
+        let log = buffer.contents();
+        let finished = log
+            .lines()
+            .filter(|line| line.contains("tool call finished"))
+            .collect::<Vec<_>>();
+        assert_eq!(finished.len(), 2);
+        assert!(finished[0].contains("tool=add id=call_1 argument_bytes=7"));
+        assert!(finished[0].contains("outcome=\"ok\""));
+        assert!(finished[0].contains("duration_ms="));
+        assert!(finished[1].contains("outcome=\"not_found\""));
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let metrics = Arc::new(crate::metrics::MemoryMetrics::new());
+        let tools = Tools::default()
+            .add_tool(AddTool)
+            .add_tool(CheckedDivTool)
+            .with_metrics(metrics.clone());
+        let _ = tools
+            .call_tools(&[
+                tool_call("1", "add", r#"{"a": 1}"#),
+                tool_call("2", "add", r#"{"b": 1}"#),
+                tool_call("3", "divide", r#"{"a": 1, "b": 0}"#),
+                tool_call("4", "divide", r#"{"a": 4, "b": 2}"#),
+                tool_call("5", "sub", r#"{"a": 1, "b": 2}"#),
+            ])
+            .await;
+
+        let add = metrics.stats("add").unwrap();
+        assert_eq!(add.calls, 2);
+        assert_eq!(add.errors.get("invalid_arguments"), Some(&1));
+        assert_eq!(add.latency.iter().sum::<u64>(), 2);
+        let divide = metrics.stats("divide").unwrap();
+        assert_eq!(divide.calls, 2);
+        assert_eq!(
+            divide.errors.into_iter().collect::<Vec<_>>(),
+            [("failed", 1)]
+        );
+        assert!(metrics.stats("sub").is_none());
+        let unknown = metrics.stats(crate::metrics::UNKNOWN_TOOL).unwrap();
+        assert_eq!(unknown.errors.get("not_found"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let path =
+            std::env::temp_dir().join(format!("ai-tools-ox-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let tools = Tools::default()
+            .add_tool(Login)
+            .add_tool(CheckedDivTool)
+            .with_audit(crate::audit::JsonlAuditSink::open(&path).unwrap());
+        let context = ToolContext::new().with(AuditContext(json!({"user": "u-1"})));
+        let _ = tools
+            .call_tools_with_context(
+                &[
+                    tool_call("1", "login", r#"{"user":"alice","password":"hunter2"}"#),
+                    tool_call("2", "divide", r#"{"a": 1, "b": 0}"#),
+                    tool_call("3", "login", r#"{"password":"hunter2""#),
+                ],
+                &context,
+            )
+            .await;
+        let _ = tools.call_one(&tool_call("4", "divide", "{}")).await;
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!log.contains("hunter2"));
+        let records = log
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 4);
+        let login = records.iter().find(|r| r["call_id"] == "1").unwrap();
+        assert_eq!(login["tool"], "login");
+        assert_eq!(
+            login["arguments"],
+            json!({"user": "alice", "password": REDACTED})
+        );
+        assert_eq!(login["context"], json!({"user": "u-1"}));
+        assert!(login["result"].is_string());
+        assert!(login["timestamp_ms"].as_u64().unwrap() > 0);
+        let failed = records.iter().find(|r| r["call_id"] == "2").unwrap();
+        assert_eq!(failed["error"], "Tool failed: division by zero");
+        assert!(failed.get("result").is_none());
+        let malformed = records.iter().find(|r| r["call_id"] == "3").unwrap();
+        assert_eq!(malformed["arguments"], REDACTED);
+        let anonymous = records.iter().find(|r| r["call_id"] == "4").unwrap();
+        assert!(anonymous.get("context").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_replace_tool() {
+        let mut tools = Tools::default().add_tool(AddTool).add_tool(Login);
+        assert!(tools.contains("add"));
+        assert!(tools.remove_tool("add").is_some());
+        assert!(tools.remove_tool("add").is_none());
+        assert!(!tools.contains("add"));
+        let result = tools.call_one(&tool_call("1", "add", r#"{"a": 1}"#)).await;
+        assert_eq!(result.to_text(), "Tool not found: add");
+
+        assert!(tools.replace_tool(AddTool).is_none());
+        let previous = tools.replace_tool(AddTool).unwrap();
+        assert_eq!(previous.to_tool().function.name, "add");
+        let result = tools.call_one(&tool_call("2", "add", r#"{"a": 1}"#)).await;
+        assert_eq!(result.to_text(), "1");
+        assert_eq!(tools.describe().len(), 2);
+        assert!(tools.names().eq(["login", "add"]));
+        assert_eq!(tools.get("add").unwrap().to_tool().function.name, "add");
+        assert!(tools.get("sub").is_none());
+        assert!(tools.iter().map(|(name, _)| name).eq(tools.names()));
+    }
+
+    #[tokio::test]
+    async fn test_merge_and_extend() {
+        let math = Tools::default().add_tool(AddTool).add_tool(CheckedDivTool);
+        let auth = Tools::default().add_tool(Login);
+        let tools = math.clone().merge(auth, ConflictPolicy::Error).unwrap();
+        assert!(tools.contains("login") && tools.contains("add"));
+
+        let error = tools
+            .clone()
+            .merge(math.clone(), ConflictPolicy::Error)
+            .unwrap_err();
+        let MergeError::Conflict { name, tools } = error;
+        assert!(math.contains(&name));
+        assert_eq!(tools.describe().len(), 3);
+        let tools = tools.merge(math, ConflictPolicy::KeepFirst).unwrap();
+        assert_eq!(tools.describe().len(), 3);
+
+        let auth = Tools::default()
+            .add_tool(Login)
+            .with_tool_timeout("login", Duration::from_secs(1));
+        auth.set_enabled("login", false);
+        let merged = Tools::default()
+            .add_tool(AddTool)
+            .merge(auth, ConflictPolicy::Error)
+            .unwrap();
+        assert!(!merged.is_enabled("login") && merged.is_enabled("add"));
+        assert_eq!(
+            merged.tool_timeouts.get("login"),
+            Some(&Duration::from_secs(1))
+        );
+
+        #[derive(Debug)]
+        struct Sum;
+
+        #[async_trait::async_trait]
+        impl ToTool for Sum {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("add")
+                    .description("adds a list of numbers")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                Ok(ToolCallResult::text(id, "sum"))
+            }
+        }
+
+        let kept = tools
+            .clone()
+            .extend(
+                [Arc::new(Sum) as Arc<dyn ToTool>],
+                ConflictPolicy::KeepFirst,
+            )
+            .unwrap();
+        let result = kept.call_one(&tool_call("1", "add", r#"{"a": 1}"#)).await;
+        assert_eq!(result.to_text(), "1");
+        let replaced = tools
+            .extend(
+                [Arc::new(Sum) as Arc<dyn ToTool>],
+                ConflictPolicy::Overwrite,
+            )
+            .unwrap();
+        let result = replaced.call_one(&tool_call("2", "add", "{}")).await;
+        assert_eq!(result.to_text(), "sum");
+    }
+
+    #[tokio::test]
+    async fn test_namespaced() {
+        let auth = Tools::default().add_tool(Login);
+        auth.set_enabled("login", false);
+        let tools = Tools::default()
+            .add_tool(AddTool)
+            .namespaced("math")
+            .unwrap()
+            .merge(
+                auth.clone().namespaced("auth").unwrap(),
+                ConflictPolicy::Error,
+            )
+            .unwrap();
+        assert!(!tools.is_enabled("auth__login"));
+        assert!(!auth.is_enabled("login"));
+        tools.set_enabled("auth__login", true);
+        let names = tools
+            .describe()
+            .into_iter()
+            .map(|d| (d.name, d.tool["function"]["name"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("math__add".to_string(), json!("math__add")),
+                ("auth__login".to_string(), json!("auth__login")),
+            ]
+        );
+
+        let results = tools
+            .call_tools(&[
+                tool_call("1", "math__add", r#"{"a": 1, "b": 2}"#),
+                tool_call("2", "add", r#"{"a": 1}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "3");
+        assert_eq!(results.0[1].to_text(), "Tool not found: add");
+        assert_eq!(
+            tools.redact_arguments("auth__login", &json!({"password": "hunter2"})),
+            json!({"password": REDACTED})
+        );
+
+        let error = Tools::default()
+            .add_tool(AddTool)
+            .namespaced("")
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::InvalidName(name) if name.is_empty()));
+        let error = Tools::default()
+            .add_tool(AddTool)
+            .namespaced(&"a".repeat(60))
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::InvalidName(name) if name.ends_with("__add")));
+        assert!(Tools::default()
+            .add_tool(AddTool)
+            .namespaced("fs.io")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subset_and_filter() {
+        let tools = Tools::default()
+            .add_tool(AddTool)
+            .add_tool(CheckedDivTool)
+            .add_tool(Login);
+        let names = serde_json::to_value(&tools).unwrap();
+        let names = names
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| &tool["function"]["name"]);
+        assert!(names.eq(&[json!("add"), json!("divide"), json!("login")]));
+        let math = tools.subset(["add", "divide", "sqrt"]);
+        assert!(math.contains("divide") && !math.contains("sqrt"));
+        let names = serde_json::to_value(&math)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["add", "divide"]);
+
+        let results = math
+            .call_tools(&[
+                tool_call("1", "add", r#"{"a": 1}"#),
+                tool_call("2", "login", r#"{"user": "a", "password": "b"}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "1");
+        assert_eq!(results.0[1].to_text(), "Tool not found: login");
+
+        let public = tools.filter(|name, _| name != "login");
+        assert_eq!(
+            public
+                .describe()
+                .into_iter()
+                .map(|d| d.name)
+                .collect::<Vec<_>>(),
+            ["add", "divide"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_enabled() {
+        let tools = Tools::default().add_tool(AddTool).add_tool(Login);
+        let shared = tools.clone();
+        tools.set_enabled("login", false);
+        assert!(!shared.is_enabled("login"));
+        let serialized = serde_json::to_value(&shared).unwrap();
+        assert_eq!(serialized.as_array().unwrap().len(), 1);
+        assert_eq!(serialized[0]["function"]["name"], "add");
+        assert_eq!(shared.describe().len(), 1);
+        assert_eq!(
+            serde_json::to_value(tools.subset(["login"])).unwrap(),
+            json!([])
+        );
+
+        let result = shared
+            .call_one(&tool_call(
+                "1",
+                "login",
+                r#"{"user": "a", "password": "b"}"#,
+            ))
+            .await;
+        assert_eq!(result.to_text(), "Tool login is currently disabled");
+
+        tools.set_enabled("login", true);
+        let result = shared
+            .call_one(&tool_call(
+                "2",
+                "login",
+                r#"{"user": "a", "password": "b"}"#,
+            ))
+            .await;
+        assert_eq!(result.to_text(), "ok");
+
+        let mut tools = tools;
+        tools.set_enabled("login", false);
+        tools.remove_tool("login");
+        tools.replace_tool(Login);
+        assert!(tools.is_enabled("login"));
+    }
+
+    #[tokio::test]
+    async fn test_call_policy() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let metrics = Arc::new(crate::metrics::MemoryMetrics::new());
+        let tools = Tools::default()
+            .add_tool(AddTool)
+            .add_tool(Counter(calls.clone()))
+            .with_metrics(metrics.clone());
+        let tool_calls = [
+            tool_call("1", "add", r#"{"a": 1}"#),
+            tool_call("2", "counter", r#"{"step": 1}"#),
+        ];
+
+        let policy = CallPolicy::new().deny(["counter"]);
+        let options = CallOptions {
+            policy: Some(&policy),
+            ..Default::default()
+        };
+        let results = tools.call_tools_with(&tool_calls, options).await;
+        assert_eq!(results.0[0].to_text(), "1");
+        assert_eq!(
+            results.0[1].to_text(),
+            "Tool counter is not allowed in this conversation"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let denied = metrics.stats("counter").unwrap();
+        assert_eq!(denied.errors.get("not_allowed"), Some(&1));
+
+        let policy = CallPolicy::new().allow(["counter"]);
+        assert!(!policy.allows("add"));
+        let options = CallOptions {
+            policy: Some(&policy),
+            ..Default::default()
+        };
+        let results = tools.call_tools_with(&tool_calls, options).await;
+        assert!(results.0[0]
+            .to_text()
+            .starts_with("Tool add is not allowed"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let policy = CallPolicy::new().allow(["add"]).deny(["add"]);
+        assert!(!policy.allows("add"));
+        assert!(CallPolicy::default().allows("anything"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_context() {
+        #[derive(Debug)]
+        struct Whoami;
+
+        #[async_trait::async_trait]
+        impl ToTool for Whoami {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("whoami")
+                    .description("the current user")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                self.call_tool_with_context(id, input, &ToolContext::new())
+                    .await
+            }
+            async fn call_tool_with_context(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+                context: &ToolContext,
+            ) -> Result<ToolCallResult, ToolError> {
+                let user = context.get::<String>().map_or("anonymous", String::as_str);
+                Ok(ToolCallResult::text(id, user))
+            }
+        }
+
+        #[derive(Debug)]
+        struct RequestId;
+
+        #[async_trait::async_trait]
+        impl ToolMiddleware for RequestId {
+            async fn around(
+                &self,
+                ctx: &ToolCall,
+                next: Next<'_>,
+            ) -> Result<ToolCallResult, ToolError> {
+                let mut result = next.run(ctx).await?;
+                if let Some(request) = next.context().get::<u64>() {
+                    result.push_text(format!("request {request}"));
+                }
+                Ok(result)
+            }
+        }
+
+        let tools = Tools::default()
+            .add_tool(Whoami)
+            .add_tool(AddTool)
+            .with_middleware(RequestId);
+        let context = ToolContext::new().with("alice".to_string()).with(7u64);
+        let results = tools
+            .call_tools_with_context(
+                &[
+                    tool_call("1", "whoami", "{}"),
+                    tool_call("2", "add", r#"{"a": 1}"#),
+                ],
+                &context,
+            )
+            .await;
+        assert_eq!(results.0[0].to_text(), "alice\nrequest 7");
+        assert_eq!(results.0[1].to_text(), "1\nrequest 7");
+        let result = tools.call_one(&tool_call("3", "whoami", "{}")).await;
+        assert_eq!(result.to_text(), "anonymous");
+
+        let mut context = ToolContext::new();
+        assert_eq!(context.insert(1u8), None);
+        assert_eq!(context.insert(2u8), Some(1));
+        assert_eq!(context.get::<u8>(), Some(&2));
+        assert!(context.get::<u16>().is_none());
+
+        let cached = Tools::default()
+            .add_tool(Whoami)
+            .with_cache(MemoryCache::new());
+        for (user, scope) in [
+            ("alice", None),
+            ("bob", None),
+            ("carol", Some("c")),
+            ("dave", Some("d")),
+        ] {
+            let mut context = ToolContext::new().with(user.to_string());
+            if let Some(scope) = scope {
+                context = context.with_scope(scope);
+            }
+            let results = cached
+                .call_tools_with_context(&[tool_call("1", "whoami", "{}")], &context)
+                .await;
+            assert_eq!(results.0[0].to_text(), user);
+        }
+        let context = ToolContext::new().with("eve".to_string()).with_scope("c");
+        let results = cached
+            .call_tools_with_context(&[tool_call("1", "whoami", "{}")], &context)
+            .await;
+        assert_eq!(results.0[0].to_text(), "carol");
+    }
+
+    #[tokio::test]
+    async fn test_typed_tool() {
+        #[derive(Debug, ai_tools_ox_derive::FromToolArgs, ai_tools_ox_derive::Object)]
+        struct Query {
+            /// search terms
+            text: String,
+            limit: Option<usize>,
+        }
+
+        #[derive(Debug, Serialize)]
+        struct Hit {
+            title: String,
+        }
+
+        #[derive(Debug)]
+        struct Search;
+
+        #[async_trait::async_trait]
+        impl TypedTool for Search {
+            type Input = Query;
+            type Output = Vec<Hit>;
+            const NAME: &'static str = "search";
+            const DESCRIPTION: &'static str = "searches the catalog";
+            async fn run(&self, input: Query) -> Result<Vec<Hit>, ToolError> {
+                if input.text.is_empty() {
+                    return Err(ToolError::Failed("empty query".to_string()));
+                }
+                let hits = (0..input.limit.unwrap_or(1))
+                    .map(|i| Hit {
+                        title: format!("{} {i}", input.text),
+                    })
+                    .collect();
+                Ok(hits)
+            }
+        }
+
+        let tool = Search.to_tool();
+        assert_eq!(tool.function.name, "search");
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "text": {"type": "string", "description": "search terms"},
+                    "limit": {"type": "integer"},
+                },
+                "required": ["text"],
+            })
+        );
+
+        let tools = Tools::default().add_tool(Search);
+        let results = tools
+            .call_tools(&[
+                tool_call("1", "search", r#"{"text": "apple", "limit": 2}"#),
+                tool_call("2", "search", r#"{"text": ""}"#),
+                tool_call("3", "search", r#"{"limit": 2}"#),
+            ])
+            .await;
+        assert_eq!(
+            results.0[0].to_text(),
+            r#"[{"title":"apple 0"},{"title":"apple 1"}]"#
+        );
+        assert_eq!(results.0[1].to_text(), "Tool failed: empty query");
+        assert_eq!(
+            results.0[2].to_text(),
+            "Invalid arguments: missing required argument `text`"
+        );
+
+        #[derive(Debug, ai_tools_ox_derive::FromToolArgs, ai_tools_ox_derive::Object)]
+        struct NoArgs {}
+
+        #[derive(Debug)]
+        struct Greet;
+
+        #[async_trait::async_trait]
+        impl TypedTool for Greet {
+            type Input = NoArgs;
+            type Output = String;
+            const NAME: &'static str = "greet";
+            const DESCRIPTION: &'static str = "greets the caller";
+            async fn run(&self, _input: Self::Input) -> Result<String, ToolError> {
+                Ok("hello stranger".to_string())
+            }
+            async fn run_with_context(
+                &self,
+                input: Self::Input,
+                context: &ToolContext,
+            ) -> Result<String, ToolError> {
+                match context.get::<String>() {
+                    Some(user) => Ok(format!("hello {user}")),
+                    None => self.run(input).await,
+                }
+            }
+        }
+
+        let tools = Tools::default().add_tool(Greet);
+        let context = ToolContext::new().with("alice".to_string());
+        let results = tools
+            .call_tools_with_context(&[tool_call("1", "greet", "{}")], &context)
+            .await;
+        assert_eq!(results.0[0].to_text(), "hello alice");
+        let result = tools.call_one(&tool_call("2", "greet", "{}")).await;
+        assert_eq!(result.to_text(), "hello stranger");
+    }
+
+    #[tokio::test]
+    async fn test_call_one() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default().add_tool(Counter(calls.clone()));
+        let result = tools
+            .call_one(&tool_call("call_1", "counter", r#"{"step":1}"#))
+            .await;
+        assert_eq!(result.tool_call_id, "call_1");
+        assert_eq!(result.content, ToolCallResult::text("call_1", 1).content);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_call_tools_dedup() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default().add_tool(Counter(calls.clone()));
+        let results = tools
+            .call_tools_dedup(&[
+                tool_call("call_1", "counter", r#"{"step":1}"#),
+                tool_call("call_2", "counter", r#"{"step":1}"#),
+                tool_call("call_3", "counter", r#"{"step":2}"#),
+            ])
+            .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let ids: Vec<_> = results.0.iter().map(|r| r.tool_call_id.as_str()).collect();
+        assert_eq!(ids, ["call_1", "call_2", "call_3"]);
+        assert_eq!(results.0[0].to_text(), "1");
+        assert_eq!(results.0[1].to_text(), "1");
+        assert_eq!(results.0[2].to_text(), "2");
+    }
+
+    #[test]
+    fn test_deprecated_parameter() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .add_deprecated_parameter::<String>("q", "use query instead")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let properties = &json["function"]["parameters"]["properties"];
+        assert_eq!(properties["q"]["deprecated"], json!(true));
+        assert!(properties["query"].get("deprecated").is_none());
+        assert_eq!(json["function"]["parameters"]["required"], json!(["query"]));
+    }
+
+    #[test]
+    fn test_parameter_schema() {
+        let tool = ToolBuilder::new()
+            .name("tag")
+            .description("tags items")
+            .add_parameter::<Vec<String>>("tags", "tags to apply")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            json["function"]["parameters"]["properties"]["tags"],
+            json!({"type": "array", "items": {"type": "string"}, "description": "tags to apply"})
+        );
+    }
+
+    #[test]
+    fn test_object_parameter() {
+        #[allow(dead_code)]
+        #[derive(ai_tools_ox_derive::Object)]
+        struct Address {
+            street: String,
+            city: String,
+            zip: Option<String>,
+        }
+        let tool = ToolBuilder::new()
+            .name("ship")
+            .description("ships a parcel")
+            .add_object_parameter::<Address>("address", "delivery address")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["address"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "street": {"type": "string"},
+                    "city": {"type": "string"},
+                    "zip": {"type": "string"},
+                },
+                "required": ["street", "city"],
+                "description": "delivery address",
+            })
+        );
+        assert_eq!(parameters["required"], json!(["address"]));
+    }
+
+    #[test]
+    fn test_option_parameter_is_optional() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .add_parameter::<Option<i32>>("limit", "max results")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(parameters["properties"]["limit"]["type"], "integer");
+        assert_eq!(parameters["required"], json!(["query"]));
+    }
+
+    #[test]
+    fn test_deprecated_tool() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .deprecated()
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(json["function"]["parameters"]["deprecated"], json!(true));
+    }
+
+    #[test]
+    fn test_multi_block_result() {
+        let mut result = ToolCallResult::new("call_1");
+        result
+            .push_text("first")
+            .push_image("image/png", "aGVsbG8=");
+        result.push_text("second");
+
+        assert_eq!(
+            result.to_openai_value(),
+            json!({
+                "role": "tool",
+                "tool_call_id": "call_1",
+                "content": "first\n[image: image/png]\nsecond",
+            })
+        );
+        assert_eq!(
+            result.to_anthropic_value(),
+            json!({
+                "type": "tool_result",
+                "tool_use_id": "call_1",
+                "content": [
+                    {"type": "text", "text": "first"},
+                    {
+                        "type": "image",
+                        "source": {"type": "base64", "media_type": "image/png", "data": "aGVsbG8="},
+                    },
+                    {"type": "text", "text": "second"},
+                ],
+            })
+        );
+    }
+
+    fn tool_value(parameters: serde_json::Value) -> serde_json::Value {
+        json!({
+            "type": "function",
+            "function": {
+                "name": "search",
+                "description": "searches",
+                "parameters": parameters,
+            }
+        })
+    }
+
+    #[test]
+    fn test_tool_try_from() {
+        let tool = Tool::try_from(tool_value(json!({
+            "type": "object",
+            "properties": {"query": {"type": "string", "description": "search query"}},
+            "required": ["query"],
+        })))
+        .unwrap();
+        assert_eq!(tool.function.name, "search");
+
+        let error = Tool::try_from(tool_value(json!({
+            "type": "object",
+            "properties": {"query": {"type": "string", "description": "search query"}},
+            "required": ["q"],
+        })))
+        .unwrap_err();
+        assert!(matches!(error, ToolValidationError::UnknownRequired(name) if name == "q"));
+
+        let error = Tool::try_from(tool_value(json!({
+            "type": "object",
+            "properties": {"query": {"type": "string", "description": "search query"}},
+            "required": [1],
+        })))
+        .unwrap_err();
+        assert!(matches!(error, ToolValidationError::RequiredNotString(value) if value == 1));
+
+        let error = Tool::try_from(tool_value(json!("string"))).unwrap_err();
+        assert!(matches!(error, ToolValidationError::ParametersNotObject));
+    }
+
+    #[test]
+    fn test_one_of() {
+        let tool = ToolBuilder::new()
+            .name("lookup")
+            .description("looks up a user")
+            .add_optional_parameter::<String>("email", "user email")
+            .add_optional_parameter::<String>("first_name", "first name")
+            .add_optional_parameter::<String>("last_name", "last name")
+            .one_of(vec![vec!["email"], vec!["first_name", "last_name"]])
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            json["function"]["parameters"]["oneOf"],
+            json!([
+                {"required": ["email"]},
+                {"required": ["first_name", "last_name"]},
+            ])
+        );
+
+        let error = ToolBuilder::new()
+            .name("lookup")
+            .description("looks up a user")
+            .add_optional_parameter::<String>("email", "user email")
+            .one_of(vec![vec!["email"], vec!["phone"]])
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::UnknownParameter(name) if name == "phone"));
+    }
+
+    #[test]
+    fn test_schema_document() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .build()
+            .unwrap();
+
+        let document = tool.to_schema_document(SchemaDialect::default());
+        assert_eq!(
+            document["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(document["type"], "object");
+        assert_eq!(document["required"], json!(["query"]));
+        assert!(document.get("$id").is_none());
+
+        let document = tool.to_schema_document_with_id(
+            SchemaDialect::Draft07,
+            "https://example.com/tools/search.json",
+        );
+        assert_eq!(
+            document["$schema"],
+            "http://json-schema.org/draft-07/schema#"
+        );
+        assert_eq!(document["$id"], "https://example.com/tools/search.json");
+    }
+
+    #[test]
+    fn test_tool_type_defaults_to_function() {
+        let value = json!({
+            "function": {
+                "name": "search",
+                "description": "searches",
+                "parameters": {"type": "object", "properties": {}, "required": []},
+            }
+        });
+        let tool: Tool = serde_json::from_value(value.clone()).unwrap();
+        assert!(matches!(tool.tool_type, ToolType::Function));
+        assert_eq!(serde_json::to_value(&tool).unwrap()["type"], "function");
+
+        let tool = Tool::try_from(value).unwrap();
+        assert!(matches!(tool.tool_type, ToolType::Function));
+    }
+
+    #[test]
+    fn test_numeric_range() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<u32>("limit", "max results")
+            .minimum("limit", 1)
+            .maximum("limit", 100)
+            .add_parameter::<f64>("score", "minimum score")
+            .exclusive_maximum("score", 1.0)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let properties = &json["function"]["parameters"]["properties"];
+        assert_eq!(properties["limit"]["minimum"], json!(1));
+        assert_eq!(properties["limit"]["maximum"], json!(100));
+        assert_eq!(properties["score"]["exclusiveMaximum"], json!(1.0));
+
+        let error = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .minimum("limit", 1)
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::UnknownParameter(name) if name == "limit"));
+    }
+
+    #[test]
+    fn test_string_constraints() {
+        let tool = ToolBuilder::new()
+            .name("signup")
+            .description("creates an account")
+            .add_parameter::<String>("username", "login name")
+            .pattern("username", "^[a-z]+$")
+            .min_length("username", 3)
+            .max_length("username", 64)
+            .add_parameter::<String>("email", "contact address")
+            .format("email", "email")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let properties = &json["function"]["parameters"]["properties"];
+        assert_eq!(properties["username"]["pattern"], "^[a-z]+$");
+        assert_eq!(properties["username"]["minLength"], json!(3));
+        assert_eq!(properties["username"]["maxLength"], json!(64));
+        assert_eq!(properties["email"]["format"], "email");
+    }
+
+    #[test]
+    fn test_array_constraints() {
+        let tool = ToolBuilder::new()
+            .name("tag")
+            .description("tags a post")
+            .add_parameter::<Vec<String>>("tags", "post tags")
+            .min_items("tags", 1)
+            .max_items("tags", 5)
+            .unique_items("tags")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let tags = &json["function"]["parameters"]["properties"]["tags"];
+        assert_eq!(tags["minItems"], json!(1));
+        assert_eq!(tags["maxItems"], json!(5));
+        assert_eq!(tags["uniqueItems"], json!(true));
+    }
+
+    #[test]
+    fn test_parameter_with_default() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .add_parameter_with_default("limit", "max results", 10u32)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["limit"],
+            json!({"type": "integer", "default": 10, "description": "max results"})
+        );
+        assert_eq!(parameters["required"], json!(["query"]));
+
+        // Map keys must serialize to strings.
+        let default = std::collections::BTreeMap::from([(vec![1u8], 1u8)]);
+        let error = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter_with_default("weights", "term weights", default)
+            .build()
+            .unwrap_err();
+        assert!(
+            matches!(error, ToolBuilderError::InvalidDefault { name, .. } if name == "weights")
+        );
+    }
+
+    #[test]
+    fn test_examples() {
+        let tool = ToolBuilder::new()
+            .name("weather")
+            .description("current weather")
+            .add_parameter::<String>("city", "city name")
+            .examples("city", ["Warsaw", "Lisbon"])
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            json["function"]["parameters"]["properties"]["city"]["examples"],
+            json!(["Warsaw", "Lisbon"])
+        );
+    }
+
+    #[test]
+    fn test_strict_tool() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .strict(true)
+            .add_parameter::<String>("query", "search query")
+            .add_optional_parameter::<u32>("limit", "max results")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(json["function"]["strict"], json!(true));
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(parameters["additionalProperties"], json!(false));
+        assert_eq!(parameters["required"], json!(["query", "limit"]));
+        assert_eq!(
+            parameters["properties"]["limit"]["type"],
+            json!(["integer", "null"])
+        );
+        assert_eq!(parameters["properties"]["query"]["type"], json!("string"));
+
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .strict(true)
+            .add_optional_parameter::<u32>("limit", "max results")
+            .add_parameter::<String>("query", "search query")
+            .add_optional_parameter::<bool>("exact", "exact match")
+            .build()
+            .unwrap();
+        let parameters = &tool.to_value().unwrap()["function"]["parameters"];
+        assert_eq!(parameters["required"], json!(["limit", "query", "exact"]));
+
+        #[allow(dead_code)]
+        #[derive(ai_tools_ox_derive::Object)]
+        struct Stop {
+            city: String,
+            note: Option<String>,
+        }
+        let tool = ToolBuilder::new()
+            .name("route")
+            .description("plans a route")
+            .strict(true)
+            .add_parameter::<Vec<Stop>>("stops", "stops on the way")
+            .build()
+            .unwrap();
+        let stop =
+            &tool.to_value().unwrap()["function"]["parameters"]["properties"]["stops"]["items"];
+        assert_eq!(stop["additionalProperties"], json!(false));
+        assert_eq!(stop["required"], json!(["city", "note"]));
+        assert_eq!(
+            stop["properties"]["note"]["type"],
+            json!(["string", "null"])
+        );
+
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_optional_parameter::<u32>("limit", "max results")
+            .strict(false)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        assert_eq!(json["function"]["strict"], json!(false));
+        assert!(json["function"]["parameters"]
+            .get("additionalProperties")
+            .is_none());
+        assert_eq!(json["function"]["parameters"]["required"], json!([]));
+    }
+
+    #[test]
+    fn test_recursive_parameter_defs() {
+        #[allow(dead_code)]
+        #[derive(ai_tools_ox_derive::Object)]
+        struct Category {
+            name: String,
+            children: Vec<Category>,
+        }
+        let tool = ToolBuilder::new()
+            .name("categorize")
+            .description("files an item")
+            .add_object_parameter::<Category>("category", "item category")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert!(parameters["properties"]["category"].get("$defs").is_none());
+        assert_eq!(
+            parameters["$defs"]["Category"]["properties"]["children"]["items"],
+            json!({"$ref": "#/$defs/Category"})
+        );
+    }
+
+    #[test]
+    fn test_schema_parameter() {
+        let tool = ToolBuilder::new()
+            .name("lookup")
+            .description("finds a record")
+            .add_schema_parameter(
+                "key",
+                "record id or name",
+                Schema::any_of([Schema::of::<u64>(), Schema::of::<String>()]),
+            )
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["key"],
+            json!({
+                "anyOf": [{"type": "integer"}, {"type": "string"}],
+                "description": "record id or name",
+            })
+        );
+        assert_eq!(parameters["required"], json!(["key"]));
+    }
+
+    #[test]
+    fn test_const_parameter() {
+        let tool = ToolBuilder::new()
+            .name("publish")
+            .description("publishes an event")
+            .add_const_parameter("version", "payload version", "v2")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["version"],
+            json!({"const": "v2", "description": "payload version"})
+        );
+        assert_eq!(parameters["required"], json!(["version"]));
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_schemars_parameters() {
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct Filter {
+            /// Text to search for.
+            query: String,
+            limit: Option<u32>,
+        }
+
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_schemars_parameters::<Filter>()
+            .add_parameter::<crate::schema::Schemars<Filter>>("fallback", "used when empty")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["query"],
+            json!({"type": "string", "description": "Text to search for."})
+        );
+        assert_eq!(parameters["required"], json!(["query", "fallback"]));
+        assert_eq!(
+            parameters["properties"]["fallback"]["properties"]["query"]["type"],
+            "string"
+        );
+    }
+
+    #[test]
+    fn test_title_and_deprecate() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .title("query", "Query")
+            .add_parameter::<String>("q", "use query instead")
+            .deprecate("q")
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let properties = &json["function"]["parameters"]["properties"];
+        assert_eq!(properties["query"]["title"], "Query");
+        assert!(properties["query"].get("deprecated").is_none());
+        assert_eq!(properties["q"]["deprecated"], json!(true));
+    }
+
+    #[test]
+    fn test_runtime_schema_parameters() {
+        let schema = crate::schema::SchemaBuilder::new()
+            .property("city", Schema::string().description("city name"))
+            .optional_property("days", Schema::integer().with("maximum", 14))
+            .build();
+        let tool = ToolBuilder::new()
+            .name("forecast")
+            .description("weather forecast")
+            .add_schema_parameters(schema)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&tool).unwrap();
+        let parameters = &json["function"]["parameters"];
+        assert_eq!(
+            parameters["properties"]["city"],
+            json!({"type": "string", "description": "city name"})
+        );
+        assert_eq!(
+            parameters["properties"]["days"],
+            json!({"type": "integer", "maximum": 14})
+        );
+        assert_eq!(parameters["required"], json!(["city"]));
+    }
+
+    /// Adds two numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - first addend
+    /// * `b` - second addend
+    #[ai_tools_ox_derive::tool]
+    async fn add(a: i64, b: Option<i64>) -> i64 {
+        a + b.unwrap_or_default()
+    }
+
+    #[ai_tools_ox_derive::tool(name = "divide", description = "divides a by b")]
+    fn checked_div(a: i64, b: i64) -> Result<i64, String> {
+        a.checked_div(b)
+            .ok_or_else(|| "division by zero".to_string())
+    }
+
+    /// Finds files of a type.
+    ///
+    /// # Arguments
+    ///
+    /// * `type` - file extension
+    #[ai_tools_ox_derive::tool]
+    fn find(r#type: String) -> String {
+        format!("*.{}", r#type)
+    }
+
+    #[tokio::test]
+    async fn test_tool_raw_identifiers() {
+        let tool = FindTool.to_tool();
+        assert_eq!(
+            tool.function.parameters.properties["type"].description,
+            "file extension"
+        );
+        let tools = Tools::default().add_tool(FindTool);
+        let result = tools
+            .call_one(&tool_call("1", "find", r#"{"type": "rs"}"#))
+            .await;
+        assert_eq!(result.to_text(), "*.rs");
+    }
+
+    #[tokio::test]
+    async fn test_tool_attribute() {
+        let tool = AddTool.to_tool();
+        assert_eq!(tool.function.name, "add");
+        assert_eq!(tool.function.description, "Adds two numbers.");
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "a": {"type": "integer", "description": "first addend"},
+                    "b": {"type": "integer", "description": "second addend"},
+                },
+                "required": ["a"],
+            })
+        );
+        assert_eq!(add(1, None).await, 1);
+
+        let tools = Tools::default().add_tool(AddTool).add_tool(CheckedDivTool);
+        let results = tools
+            .call_tools(&[
+                tool_call("1", "add", r#"{"a": 2, "b": 3}"#),
+                tool_call("2", "divide", r#"{"a": 1, "b": 0}"#),
+                tool_call("3", "add", r#"{"b": 3}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "5");
+        assert_eq!(results.0[1].to_text(), "Tool failed: division by zero");
+        assert!(results.0[2]
+            .to_text()
+            .starts_with("Invalid arguments: missing required argument `a`"));
+    }
+
+    #[test]
+    fn test_tool_from_fn() {
+        let tool = ai_tools_ox_derive::tool_from_fn!(self::checked_div);
+        assert_eq!(tool.function.name, "divide");
+        assert_eq!(tool.function.description, "divides a by b");
+        assert_eq!(
+            serde_json::to_value(&tool).unwrap(),
+            serde_json::to_value(ai_tools_ox_derive::tool_from_fn!(checked_div)).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_derive_to_tool() {
+        #[derive(Debug, ai_tools_ox_derive::FromToolArgs, ai_tools_ox_derive::Object)]
+        struct SearchArgs {
+            /// search terms
+            query: String,
+            limit: Option<usize>,
+        }
+
+        /// Searches the catalogue.
+        #[derive(Debug, ai_tools_ox_derive::ToTool)]
+        struct CatalogueSearch {
+            items: Vec<&'static str>,
+        }
+
+        impl CatalogueSearch {
+            async fn run(&self, args: SearchArgs) -> Result<Vec<&'static str>, String> {
+                if args.query.is_empty() {
+                    return Err("empty query".to_string());
+                }
+                let matches = self.items.iter().filter(|item| item.contains(&args.query));
+                Ok(matches.take(args.limit.unwrap_or(10)).copied().collect())
+            }
+        }
+
+        let search = CatalogueSearch {
+            items: vec!["red apple", "green apple", "pear"],
+        };
+        let tool = search.to_tool();
+        assert_eq!(tool.function.name, "catalogue_search");
+        assert_eq!(tool.function.description, "Searches the catalogue.");
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "search terms"},
+                    "limit": {"type": "integer"},
+                },
+                "required": ["query"],
+            })
+        );
+
+        let result = search
+            .call_tool("1", json!({"query": "apple", "limit": 1}))
+            .await
+            .unwrap();
+        assert_eq!(result.to_text(), r#"["red apple"]"#);
+        let error = search.call_tool("2", json!({"query": ""})).await;
+        assert!(matches!(error, Err(ToolError::Failed(e)) if e == "empty query"));
+        let error = search
+            .call_tool("3", json!({"query": "apple", "limit": "one"}))
+            .await;
+        assert!(matches!(
+            error,
+            Err(ToolError::InvalidArguments(e)) if e.starts_with("argument `limit` is invalid")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tool_macro() {
+        #[derive(Debug, serde::Deserialize, ai_tools_ox_derive::Object)]
+        #[serde(rename_all = "lowercase")]
+        enum Unit {
+            Celsius,
+            Fahrenheit,
+        }
+
+        let weather = tool! {
+            name: "get_weather",
+            description: "current weather",
+            params: { city: String ["city name"], unit?: Unit },
+            |args| async move {
+                let unit = match args.unit {
+                    Some(Unit::Fahrenheit) => "F",
+                    _ => "C",
+                };
+                Ok::<_, String>(format!("{}: 20{unit}", args.city))
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&weather.to_tool().function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string", "description": "city name"},
+                    "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                },
+                "required": ["city"],
+            })
+        );
+
+        let tools = Tools::default().add_tool(weather);
+        let results = tools
+            .call_tools(&[
+                tool_call(
+                    "1",
+                    "get_weather",
+                    r#"{"city": "Oslo", "unit": "fahrenheit"}"#,
+                ),
+                tool_call("2", "get_weather", r#"{"unit": "celsius"}"#),
+                tool_call("3", "get_weather", r#"{"city": "Oslo", "unit": "kelvin"}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "Oslo: 20F");
+        assert_eq!(
+            results.0[1].to_text(),
+            "Invalid arguments: missing required argument `city`"
+        );
+        assert!(results.0[2].to_text().starts_with(
+            "Invalid arguments: argument `unit` is invalid: unknown variant `kelvin`"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_toolbox() {
+        struct Notes {
+            notes: std::sync::Mutex<Vec<String>>,
+        }
+
+        #[ai_tools_ox_derive::toolbox]
+        impl Notes {
+            /// Saves a note.
+            #[tool]
+            async fn save(&self, text: String) -> usize {
+                let mut notes = self.notes.lock().unwrap();
+                notes.push(text);
+                notes.len()
+            }
+
+            #[tool(name = "list_notes", description = "lists saved notes")]
+            fn list(&self) -> Vec<String> {
+                self.notes.lock().unwrap().clone()
+            }
+
+            #[allow(dead_code)]
+            fn clear(&self) {
+                self.notes.lock().unwrap().clear();
+            }
+        }
+
+        let notes = Arc::new(Notes {
+            notes: Default::default(),
+        });
+        let tools = notes.clone().toolbox();
+        let mut names = tools.names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["list_notes", "save"]);
+
+        let results = tools
+            .call_tools(&[
+                tool_call("1", "save", r#"{"text": "milk"}"#),
+                tool_call("2", "list_notes", "{}"),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "1");
+        assert_eq!(results.0[1].to_text(), r#"["milk"]"#);
+        assert_eq!(notes.notes.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tool_definition() {
+        /// Searches the web.
+        #[allow(dead_code)]
+        #[derive(ai_tools_ox_derive::Object)]
+        #[tool(name = "search")]
+        struct WebSearch {
+            /// search terms
+            query: String,
+            page: Option<u32>,
+        }
+        let tool = WebSearch::tool_definition();
+        assert_eq!(tool.function.name, "search");
+        assert_eq!(tool.function.description, "Searches the web.");
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "search terms"},
+                    "page": {"type": "integer"},
+                },
+                "required": ["query"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parameter_order() {
+        let tool = ToolBuilder::new()
+            .name("order")
+            .description("keeps parameter order")
+            .add_parameter::<String>("zone", "")
+            .add_parameter::<String>("area", "")
+            .add_parameter::<String>("market", "")
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&tool.function.parameters).unwrap();
+        let position = |name: &str| json.find(&format!("\"{name}\"")).unwrap();
+        assert!(position("zone") < position("area"));
+        assert!(position("area") < position("market"));
+    }
+
+    #[test]
+    fn test_struct_parameters() {
+        #[allow(dead_code)]
+        #[derive(ai_tools_ox_derive::Object)]
+        struct ListArgs {
+            /// page to fetch
+            page: u32,
+            filter: Option<String>,
+        }
+        let tool = ToolBuilder::new()
+            .name("list")
+            .description("lists items")
+            .add_struct_parameters::<ListArgs>()
+            .minimum("page", 1)
+            .add_parameter::<bool>("archived", "include archived items")
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "page": {"type": "integer", "minimum": 1, "description": "page to fetch"},
+                    "filter": {"type": "string"},
+                    "archived": {"type": "boolean", "description": "include archived items"},
+                },
+                "required": ["page", "archived"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_enum_parameter_of() {
+        #[allow(dead_code)]
+        #[derive(serde::Deserialize, ai_tools_ox_derive::EnumJsonify)]
+        #[serde(rename_all = "snake_case")]
+        enum Sort {
+            Newest,
+            MostRead,
+            #[serde(rename = "az")]
+            Alphabetical,
+            #[serde(skip)]
+            Internal,
+        }
+        let tool = ToolBuilder::new()
+            .name("articles")
+            .description("lists articles")
+            .add_enum_parameter_of::<Sort>("sort", "sort order")
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&tool.function.parameters).unwrap()["properties"]["sort"],
+            json!({
+                "type": "string",
+                "description": "sort order",
+                "enum": ["newest", "most_read", "az"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_array_and_map_parameters() {
+        let tool = ToolBuilder::new()
+            .name("tag")
+            .description("tags a document")
+            .add_array_parameter::<String>("tags", "labels", |tags| {
+                tags.min_items(1)
+                    .max_items(10)
+                    .unique_items()
+                    .item("minLength", 1)
+            })
+            .add_map_parameter::<f64>("weights", "weight per label", |weights| {
+                weights.max_properties(10).value("minimum", 0)
+            })
+            .build()
+            .unwrap();
+        let parameters = serde_json::to_value(&tool.function.parameters).unwrap();
+        assert_eq!(
+            parameters["properties"]["tags"],
+            json!({
+                "type": "array",
+                "items": {"type": "string", "minLength": 1},
+                "minItems": 1,
+                "maxItems": 10,
+                "uniqueItems": true,
+                "description": "labels",
+            })
+        );
+        assert_eq!(
+            parameters["properties"]["weights"],
+            json!({
+                "type": "object",
+                "additionalProperties": {"type": "number", "minimum": 0},
+                "maxProperties": 10,
+                "description": "weight per label",
+            })
+        );
+        assert_eq!(parameters["required"], json!(["tags", "weights"]));
+    }
+
+    #[test]
+    fn test_build_validation() {
+        let error = ToolBuilder::new()
+            .name("weather.current")
+            .description("current weather")
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::InvalidName(name) if name == "weather.current"));
+        assert!(matches!(
+            ToolBuilder::new().name("").description("empty").build(),
+            Err(ToolBuilderError::InvalidName(_))
+        ));
+        assert!(matches!(
+            ToolBuilder::new()
+                .name(&"a".repeat(65))
+                .description("long")
+                .build(),
+            Err(ToolBuilderError::InvalidName(_))
+        ));
+        assert!(ToolBuilder::new()
+            .name(&"a".repeat(64))
+            .description("long")
+            .build()
+            .is_ok());
+
+        assert!(matches!(
+            ToolBuilder::new()
+                .name("describe")
+                .description(&"é".repeat(1025))
+                .build(),
+            Err(ToolBuilderError::DescriptionTooLong(1025))
+        ));
+
+        let error = ToolBuilder::new()
+            .name("upload")
+            .description("uploads a file")
+            .add_parameter::<String>("content", "x".repeat(MAX_SCHEMA_SIZE))
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, ToolBuilderError::SchemaTooLarge(size) if size > MAX_SCHEMA_SIZE));
+    }
+
+    #[test]
+    fn test_to_value() {
+        let tool = ToolBuilder::new()
+            .name("search")
+            .description("searches")
+            .add_parameter::<String>("query", "search query")
+            .build()
+            .unwrap();
+        let json = tool.to_value().unwrap();
+        assert_eq!(json, &serde_json::to_value(&tool).unwrap());
+        assert!(std::ptr::eq(json, tool.to_openai_value().unwrap()));
+        assert_eq!(json["function"]["name"], "search");
+        assert_eq!(tool.clone().to_value().unwrap(), json);
+
+        let mut renamed = tool.clone();
+        renamed.function_mut().name = "find".to_string();
+        assert_eq!(renamed.to_value().unwrap()["function"]["name"], "find");
+        assert_eq!(tool.to_value().unwrap()["function"]["name"], "search");
+
+        let literal = Tool::new(tool.function().clone());
+        assert_eq!(literal.to_value().unwrap(), tool.to_value().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_try_call_one() {
+        let tools = Tools::default().add_tool(AddTool).add_tool(CheckedDivTool);
+        let result = tools
+            .try_call_one(&tool_call("1", "add", r#"{"a": 2, "b": 3}"#))
+            .await
+            .unwrap();
+        assert_eq!(result.to_text(), "5");
+        assert!(matches!(
+            tools
+                .try_call_one(&tool_call("2", "add", "{\"a\": 2"))
+                .await,
+            Err(ToolError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            tools.try_call_one(&tool_call("3", "sub", "{}")).await,
+            Err(ToolError::NotFound(name)) if name == "sub"
+        ));
+        assert!(matches!(
+            tools
+                .try_call_one(&tool_call("4", "divide", r#"{"a": 1, "b": 0}"#))
+                .await,
+            Err(ToolError::Failed(message)) if message == "division by zero"
+        ));
+
+        let result = tools.call_one(&tool_call("5", "add", "not json")).await;
+        assert_eq!(result.tool_call_id, "5");
+        assert!(result.to_text().starts_with("Invalid arguments: "));
+    }
+
+    #[tokio::test]
+    async fn test_call_tools_concurrently() {
+        #[derive(Debug)]
+        struct Rendezvous(Arc<tokio::sync::Barrier>);
+
+        #[async_trait::async_trait]
+        impl ToTool for Rendezvous {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("rendezvous")
+                    .description("waits for the other calls")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                self.0.wait().await;
+                Ok(ToolCallResult::text(id, id))
+            }
+        }
+
+        let tools = Tools::default().add_tool(Rendezvous(Arc::new(tokio::sync::Barrier::new(3))));
+        let calls = ["a", "b", "c"].map(|id| tool_call(id, "rendezvous", "{}"));
+        let results =
+            tokio::time::timeout(std::time::Duration::from_secs(5), tools.call_tools(&calls))
+                .await
+                .expect("calls run concurrently");
+        let texts = results.0.iter().map(|r| r.to_text()).collect::<Vec<_>>();
+        assert_eq!(texts, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrency() {
+        #[derive(Debug, Default)]
+        struct Busy {
+            running: AtomicUsize,
+            peak: Arc<AtomicUsize>,
+        }
+
+        #[async_trait::async_trait]
+        impl ToTool for Busy {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("busy")
+                    .description("takes a while")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(running, Ordering::SeqCst);
+                for _ in 0..3 {
+                    tokio::task::yield_now().await;
+                }
+                self.running.fetch_sub(1, Ordering::SeqCst);
+                Ok(ToolCallResult::text(id, "done"))
+            }
+        }
+
+        let peak = Arc::new(AtomicUsize::new(0));
+        let tools = Tools::default()
+            .add_tool(Busy {
+                peak: peak.clone(),
+                ..Default::default()
+            })
+            .with_max_concurrency(2);
+        let calls = (0..5)
+            .map(|i| tool_call(&i.to_string(), "busy", "{}"))
+            .collect::<Vec<_>>();
+        let results = tools.call_tools(&calls).await;
+        assert_eq!(results.0.len(), 5);
+        assert!(results.0.iter().all(|r| r.to_text() == "done"));
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "max concurrency must be positive")]
+    fn test_zero_concurrency() {
+        let _ = Tools::default().with_max_concurrency(0);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        #[derive(Debug)]
+        struct Sleep(&'static str);
+
+        #[async_trait::async_trait]
+        impl ToTool for Sleep {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name(self.0)
+                    .description("sleeps for a moment")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(ToolCallResult::text(id, "awake"))
+            }
+        }
+
+        let tools = Tools::default()
+            .add_tool(Sleep("nap"))
+            .add_tool(Sleep("sleep"))
+            .add_tool(AddTool)
+            .with_timeout(Duration::from_millis(10))
+            .with_tool_timeout("sleep", Duration::from_secs(5));
+        let results = tools
+            .call_tools(&[
+                tool_call("1", "nap", "{}"),
+                tool_call("2", "sleep", "{}"),
+                tool_call("3", "add", r#"{"a": 1}"#),
+            ])
+            .await;
+        assert_eq!(results.0[0].to_text(), "Tool timed out after 10ms");
+        assert_eq!(results.0[1].to_text(), "awake");
+        assert_eq!(results.0[2].to_text(), "1");
+    }
+
+    #[tokio::test]
+    async fn test_call_tools_with_cancel() {
+        #[derive(Debug)]
+        struct Hang;
+
+        #[async_trait::async_trait]
+        impl ToTool for Hang {
+            fn to_tool(&self) -> Tool {
+                ToolBuilder::new()
+                    .name("hang")
+                    .description("never returns")
+                    .build()
+                    .unwrap()
+            }
+            async fn call_tool(
+                &self,
+                _id: &str,
+                _input: serde_json::Value,
+            ) -> Result<ToolCallResult, ToolError> {
+                std::future::pending().await
+            }
+        }
+
+        let metrics = Arc::new(crate::metrics::MemoryMetrics::new());
+        let tools = Tools::default()
+            .add_tool(Hang)
+            .add_tool(AddTool)
+            .with_metrics(metrics.clone());
+        let cancel = CancellationToken::new();
+        let calls = [
+            tool_call("1", "add", r#"{"a": 1}"#),
+            tool_call("2", "hang", "{}"),
+            tool_call("3", "hang", "{}"),
+        ];
+        let options = CallOptions {
+            cancel: Some(&cancel),
+            dedup: true,
+            ..Default::default()
+        };
+        let (results, _) = tokio::join!(tools.call_tools_with(&calls, options), async {
+            tokio::task::yield_now().await;
+            cancel.cancel();
+        });
+        assert_eq!(results.0[0].to_text(), "1");
+        assert_eq!(results.0[1].tool_call_id, "2");
+        assert_eq!(results.0[1].to_text(), "Tool call was cancelled");
+        assert_eq!(results.0[2].tool_call_id, "3");
+        assert_eq!(results.0[2].to_text(), "Tool call was cancelled");
+        let hang = metrics.stats("hang").unwrap();
+        assert_eq!(hang.calls, 1);
+        assert_eq!(hang.errors.get("cancelled"), Some(&1));
+    }
+}
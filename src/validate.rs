@@ -0,0 +1,138 @@
+use std::fmt;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Checks `value` against the subset of JSON Schema this crate emits: `type`,
+/// `enum`, `properties`, `required`, `additionalProperties` and `items`.
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(schema, value, "$", &mut violations);
+    violations
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let mut violation = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+    let types = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| is_type(name, value)) {
+        violation(format!(
+            "expected {}, found {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+    if let Some(Value::Array(variants)) = schema.get("enum") {
+        if !variants.contains(value) {
+            violation(format!(
+                "{value} is not one of {}",
+                Value::from(variants.clone())
+            ));
+        }
+    }
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(name) {
+                        violation(format!("missing required property `{name}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, value) in map {
+                let path = format!("{path}.{key}");
+                match (
+                    properties.and_then(|p| p.get(key)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(schema), _) => validate_at(schema, value, &path, violations),
+                    (None, Some(Value::Bool(false))) => violations.push(SchemaViolation {
+                        path,
+                        message: "unexpected property".to_string(),
+                    }),
+                    (None, Some(schema @ Value::Object(_))) => {
+                        validate_at(schema, value, &path, violations)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(schema, item, &format!("{path}[{i}]"), violations);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        name => type_name(value) == name,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "count": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "unit": {"type": "string", "enum": ["c", "f"]},
+            },
+            "required": ["count"],
+            "additionalProperties": false,
+        });
+        assert!(validate(&schema, &json!({"count": 1, "tags": ["a"], "unit": "c"})).is_empty());
+
+        let paths = validate(&schema, &json!({"tags": [1], "unit": "k", "extra": true}))
+            .into_iter()
+            .map(|v| v.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["$", "$.tags[0]", "$.unit", "$.extra"]);
+    }
+}
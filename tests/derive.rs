@@ -0,0 +1,36 @@
+#![cfg(feature = "derive")]
+
+// Shadow the names the generated code would otherwise pick up from the
+// caller's scope, so the derives must use fully qualified paths.
+#[allow(dead_code)]
+mod serde_json {}
+#[allow(dead_code)]
+struct Jsonify;
+#[allow(dead_code)]
+struct JsonifyConfig;
+
+#[allow(dead_code)]
+#[derive(ai_tools_ox::Object)]
+struct Query {
+    /// search terms
+    text: String,
+    limit: Option<u32>,
+    filter: Filter,
+}
+
+#[allow(dead_code)]
+#[derive(ai_tools_ox::Object)]
+enum Filter {
+    All,
+    Tag { name: String },
+}
+
+#[test]
+fn test_derive_reexport() {
+    let schema = <Query as ai_tools_ox::Jsonify>::jsonify();
+    assert_eq!(schema["required"], ::serde_json::json!(["text", "filter"]));
+    assert_eq!(
+        schema["properties"]["text"]["description"],
+        ::serde_json::json!("search terms")
+    );
+}
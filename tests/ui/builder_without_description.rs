@@ -0,0 +1,5 @@
+use ai_tools_ox::tools::ToolBuilder;
+
+fn main() {
+    let _ = ToolBuilder::new().name("search").build();
+}
@@ -0,0 +1,9 @@
+use ai_tools_ox_derive::tool;
+
+/// Answers with pong.
+#[tool(name = "ping pong")]
+async fn ping() -> String {
+    "pong".to_string()
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use ai_tools_ox_derive::Object;
+
+struct Bar;
+
+#[derive(Object)]
+struct Foo {
+    bar: Bar,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use ai_tools_ox::tools::{tool, ToolError};
+
+fn main() {
+    let _ping = tool! {
+        name: "ping pong",
+        description: "answers with pong",
+        params: {},
+        |_args| async move { Ok::<_, ToolError>("pong") },
+    };
+}
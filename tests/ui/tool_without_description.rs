@@ -0,0 +1,8 @@
+use ai_tools_ox_derive::tool;
+
+#[tool]
+async fn ping() -> String {
+    "pong".to_string()
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ai_tools_ox_derive::Object;
+
+#[derive(Object)]
+struct Foo {
+    #[description(minimun = 1)]
+    count: u32,
+}
+
+fn main() {}
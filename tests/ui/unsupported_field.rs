@@ -0,0 +1,8 @@
+use ai_tools_ox_derive::Object;
+
+#[derive(Object)]
+struct Foo {
+    callback: fn(i32) -> i32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use ai_tools_ox_derive::Object;
+
+#[derive(Object)]
+#[serde(rename_all = "Title Case")]
+struct Args {
+    /// Query.
+    query: String,
+}
+
+fn main() {}